@@ -4,7 +4,11 @@ use winit::{
     window::{WindowBuilder, Window}, dpi::PhysicalSize,
     keyboard::{PhysicalKey, KeyCode}
 };
-use crate::{draw::DrawState, vertex::Vertex, geometry::GeometryType};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+use wgpu::util::DeviceExt;
+use crate::{draw::DrawState, vertex::Vertex, geometry::{GeometryType, line::LineSegmentRaw}, camera::{Camera, CameraUniform}, texture::Texture, capture::{Image, FrameSequence}, transform::ModelUniform, instance::{Instance, InstanceRaw}, light::Lighting, controller::OrbitController, input::InputState, time::Clock};
+use cgmath::Point3;
 
 #[cfg(target_arch="wasm32")]
 use wasm_bindgen::prelude::*;
@@ -15,12 +19,24 @@ pub type ViewFn<Model> = fn(&mut App, &Model);
 
 pub type UpdateFn<Model> = fn(&App, &mut Model);
 
+pub type EventFn<Model> = fn(&App, &mut Model, &WindowEvent);
+
 pub struct AppBuilder<M = ()> {
     model: ModelFn<M>,
     update: Option<UpdateFn<M>>,
     view: Option<ViewFn<M>>,
+    event: Option<EventFn<M>>,
     window_size: Option<winit::dpi::PhysicalSize<u32>>,
     title: Option<String>,
+    capture: Option<FrameSequence>,
+    orbit_controls: bool,
+    fixed_update_rate: Option<f32>,
+}
+
+impl Default for AppBuilder {
+    fn default() -> AppBuilder<()> {
+        AppBuilder::new()
+    }
 }
 
 impl AppBuilder{
@@ -30,8 +46,12 @@ impl AppBuilder{
             model,
             update: None,
             view: None,
+            event: None,
             window_size: None,
             title: None,
+            capture: None,
+            orbit_controls: true,
+            fixed_update_rate: None,
         }
     }
 
@@ -74,12 +94,53 @@ impl<M> AppBuilder<M> where M: 'static {
 
         let _ = window.request_inner_size(self.window_size.unwrap_or(PhysicalSize::new(1080, 1080)));
 
-        let app = App::new(&window).await;
+        let mut app = App::new(&window).await;
+        app.orbit_controller_mut().enabled = self.orbit_controls;
 
         let model = (self.model)(&app);
 
         
-        run_loop(app, event_loop, model, self.view, self.update);
+        let callbacks = Callbacks {
+            update: self.update,
+            view: self.view,
+            event: self.event,
+            fixed_step: self.fixed_update_rate.map(|hz| 1. / hz),
+        };
+        run_loop(app, event_loop, model, callbacks, self.capture);
+    }
+
+    /// Runs the sketch without a window for `frames` frames and returns the
+    /// RGBA pixels of every rendered frame.
+    ///
+    /// Not available on the web, where frames can't be waited on to read them back.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn render_to_texture(self, width: u32, height: u32, frames: u32) -> Vec<Image> {
+        let mut app = App::new_headless(width, height).await;
+
+        let mut model = (self.model)(&app);
+
+        let callbacks = Callbacks {
+            update: self.update,
+            view: self.view,
+            event: self.event,
+            fixed_step: self.fixed_update_rate.map(|hz| 1. / hz),
+        };
+        // Without a display to keep pace with, every frame advances the clock by exactly
+        // one step so the output doesn't depend on how fast the machine renders.
+        let step = callbacks.fixed_step.unwrap_or(1. / 60.);
+
+        (0..frames).map(|_| {
+            callbacks.frame(&mut app, &mut model, step);
+            let image = app.render_offscreen();
+            let frame = app.frame_count();
+            if let Some(capture) = &self.capture {
+                if let Err(e) = capture.save(frame, &image) {
+                    log::error!("Couldn't save frame {}: {}", frame, e);
+                }
+            }
+            app.clock.end_frame();
+            image
+        }).collect()
     }
 
     pub fn app(model: ModelFn<M>) -> AppBuilder<M>{
@@ -87,41 +148,99 @@ impl<M> AppBuilder<M> where M: 'static {
             model,
             update: None,
             view: None,
+            event: None,
             window_size: None,
             title: None,
+            capture: None,
+            orbit_controls: true,
+            fixed_update_rate: None,
         }
     }
 
     pub fn update(mut self, u: UpdateFn<M>) -> AppBuilder<M>{
         self.update = Some(u);
-        return self;
+        self
     }
 
     pub fn view(mut self, v: ViewFn<M>) -> AppBuilder<M>{
         self.view = Some(v);
-        return self;
+        self
+    }
+
+    /// Called with every window event that isn't consumed by the camera controls.
+    pub fn event(mut self, e: EventFn<M>) -> AppBuilder<M> {
+        self.event = Some(e);
+        self
     }
 
     pub fn title(mut self, t: String) -> AppBuilder<M> {
         self.title = Some(t);
-        return self;
+        self
+    }
+
+    /// Calls `update` exactly `hz` times per simulated second, running several
+    /// updates in one frame to catch up when rendering is slower than that.
+    ///
+    /// Panics unless `hz` is finite and positive.
+    pub fn fixed_update_rate(mut self, hz: f32) -> AppBuilder<M> {
+        assert!(hz.is_finite() && hz > 0., "fixed update rate must be finite and positive, got {}", hz);
+        self.fixed_update_rate = Some(hz);
+        self
+    }
+
+    /// Enables or disables the mouse driven orbit, pan and zoom camera controls, which are on by default.
+    pub fn orbit_controls(mut self, enabled: bool) -> AppBuilder<M> {
+        self.orbit_controls = enabled;
+        self
+    }
+
+    /// Saves every `every`th rendered frame to `directory` as `frame_00000.png`, `frame_00001.png`, ...
+    ///
+    /// Not available on the web, where frames can't be waited on to read them back.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn capture_frames(mut self, every: u32, directory: impl Into<PathBuf>) -> AppBuilder<M> {
+        self.capture = Some(FrameSequence::new(every, directory));
+        self
     }
 }
 
 pub struct App<'a> {
-    window: &'a Window,
-    surface: wgpu::Surface<'a>,
+    window: Option<&'a Window>,
+    surface: Option<wgpu::Surface<'a>>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
     size: winit::dpi::PhysicalSize<u32>,
     triangle_render_pipeline: wgpu::RenderPipeline,
     line_render_pipeline: wgpu::RenderPipeline,
+    triangle_overlay_pipeline: wgpu::RenderPipeline,
+    line_overlay_pipeline: wgpu::RenderPipeline,
+    thick_line_render_pipeline: wgpu::RenderPipeline,
+    thick_line_overlay_pipeline: wgpu::RenderPipeline,
+    depth_texture: Texture,
+    #[cfg(not(target_arch = "wasm32"))]
+    offscreen_texture: Option<wgpu::Texture>,
+    camera: Camera,
+    controller: OrbitController,
+    input_state: InputState,
+    clock: Clock,
+    camera_uniform: CameraUniform,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    light_buffer: wgpu::Buffer,
+    model_bind_group_layout: wgpu::BindGroupLayout,
+    model_buffer: wgpu::Buffer,
+    model_bind_group: wgpu::BindGroup,
+    model_capacity: u64,
+    model_stride: u64,
+    /// The model uniforms as last uploaded, so only the slots that change are written.
+    uploaded_models: Vec<u8>,
+    default_instance_buffer: wgpu::Buffer,
     draw_state: DrawState,
 }
 
 impl<'a> App<'a>{
-    async fn new(window: &'a Window) -> App{
+    async fn new(window: &'a Window) -> App<'a>{
         let size = window.inner_size();
 
         // The instance is a handle to our GPU
@@ -148,20 +267,7 @@ impl<'a> App<'a>{
             },
         ).await.unwrap();
 
-        let (device, queue) = adapter.request_device(
-            &wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
-                // WebGL doesn't support all of wgpu's features, so if
-                // we're building for the web, we'll have to disable some.
-               required_limits: if cfg!(target_arch = "wasm32") {
-                    wgpu::Limits::downlevel_webgl2_defaults()
-                } else {
-                    wgpu::Limits::default()
-                },
-                label: None,
-            },
-            None, // Trace path
-        ).await.unwrap();       
+        let (device, queue) = request_device(&adapter).await;
 
         let surface_caps = surface.get_capabilities(&adapter);
         // Shader code in this tutorial assumes an sRGB surface texture. Using a different
@@ -169,12 +275,14 @@ impl<'a> App<'a>{
         // sRGB surfaces, you'll need to account for that when drawing to the frame.
         let surface_format = surface_caps.formats.iter()
             .copied()
-            .filter(|f| f.is_srgb())
-            .next()
+            .find(|f| f.is_srgb())
             .unwrap_or(surface_caps.formats[0]);
 
+        // Frames are copied out of the surface to capture them, where the surface allows it.
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT
+            | (surface_caps.usages & wgpu::TextureUsages::COPY_SRC);
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface_format,
             width: size.width,
             height: size.height,
@@ -190,83 +298,256 @@ impl<'a> App<'a>{
         #[cfg(not(target_arch="wasm32"))]
         surface.configure(&device, &config);
 
+        App::from_device(Some(window), Some(surface), device, queue, config)
+    }
+
+    /// Creates an app that renders into an offscreen texture instead of a window.
+    ///
+    /// When no hardware adapter is available this falls back to a software adapter,
+    /// so it also works on machines without a GPU.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn new_headless(width: u32, height: u32) -> App<'a> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let mut adapter = instance.request_adapter(
+            &wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            },
+        ).await;
+
+        if adapter.is_none() {
+            adapter = instance.request_adapter(
+                &wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::default(),
+                    compatible_surface: None,
+                    force_fallback_adapter: true,
+                },
+            ).await;
+        }
+        let adapter = adapter.expect("Couldn't find a hardware or fallback adapter");
+
+        let (device, queue) = request_device(&adapter).await;
+
+        // There is no surface to negotiate a format with, so we pick an sRGB format
+        // whose texels can be read back directly as RGBA.
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+
+        App::from_device(None, None, device, queue, config)
+    }
+
+    fn from_device(
+        window: Option<&'a Window>,
+        surface: Option<wgpu::Surface<'a>>,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        config: wgpu::SurfaceConfiguration,
+    ) -> App<'a> {
+        let size = PhysicalSize::new(config.width, config.height);
+
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Shader"),
             source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
         });  
 
+        let camera = Camera::new(size.width as f32 / size.height.max(1) as f32);
+
+        let mut camera_uniform = CameraUniform::new();
+        camera_uniform.update_view_proj(&camera);
+
+        let camera_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Camera Buffer"),
+                contents: bytemuck::cast_slice(&[camera_uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let light_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Light Buffer"),
+                contents: bytemuck::cast_slice(&[Lighting::new().to_uniform()]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }
+            ],
+            label: Some("camera_bind_group_layout"),
+        });
+
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &camera_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: light_buffer.as_entire_binding(),
+                }
+            ],
+            label: Some("camera_bind_group"),
+        });
+
+        // Every geometry gets its own slot in a single uniform buffer, selected
+        // with a dynamic offset when it is drawn.
+        let model_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<ModelUniform>() as u64),
+                    },
+                    count: None,
+                }
+            ],
+            label: Some("model_bind_group_layout"),
+        });
+
+        let model_stride = (std::mem::size_of::<ModelUniform>() as u64)
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+        let model_capacity = 16;
+        let (model_buffer, model_bind_group) =
+            create_model_buffer(&device, &model_bind_group_layout, model_capacity, model_stride);
+
+        // Geometry that isn't instanced is drawn as a single untransformed instance.
+        let default_instance_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Default Instance Buffer"),
+                contents: bytemuck::cast_slice(&[Instance::default().to_raw()]),
+                usage: wgpu::BufferUsages::VERTEX,
+            }
+        );
+
         let render_pipeline_layout =
         device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[],
+            bind_group_layouts: &[&camera_bind_group_layout, &model_bind_group_layout],
             push_constant_ranges: &[],
 
         });
 
-        let triangle_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Triangle Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main", // 1.
-                buffers: &[Vertex::desc()], // 2.
-            },
-            fragment: Some(wgpu::FragmentState { // 3.
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState { // 4.
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList, // 1.
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw, // 2.
-                cull_mode: Some(wgpu::Face::Back),
-                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                polygon_mode: wgpu::PolygonMode::Fill,
-                // Requires Features::DEPTH_CLIP_CONTROL
-                unclipped_depth: false,
-                // Requires Features::CONSERVATIVE_RASTERIZATION
-                conservative: false,
-            },
-            depth_stencil: None, // 1.
-            multisample: wgpu::MultisampleState {
-                count: 1, // 2.
-                mask: !0, // 3.
-                alpha_to_coverage_enabled: false, // 4.
-            },
-            multiview: None, // 5.
-        });
+        let triangle_primitive = wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList, // 1.
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw, // 2.
+            cull_mode: Some(wgpu::Face::Back),
+            // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
+            polygon_mode: wgpu::PolygonMode::Fill,
+            // Requires Features::DEPTH_CLIP_CONTROL
+            unclipped_depth: false,
+            // Requires Features::CONSERVATIVE_RASTERIZATION
+            conservative: false,
+        };
 
-        let line_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Line Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main", // 1.
-                buffers: &[Vertex::desc()], // 2.
-            },
-            fragment: Some(wgpu::FragmentState { // 3.
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState { // 4.
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState{
-                topology: wgpu::PrimitiveTopology::LineList,
-                ..Default::default()
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
+        let line_primitive = wgpu::PrimitiveState{
+            topology: wgpu::PrimitiveTopology::LineList,
+            ..Default::default()
+        };
+
+        let triangle_render_pipeline = create_render_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &shader,
+            config.format,
+            triangle_primitive,
+            true,
+            "Triangle Render Pipeline",
+        );
+
+        let line_render_pipeline = create_render_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &shader,
+            config.format,
+            line_primitive,
+            true,
+            "Line Render Pipeline",
+        );
+
+        let triangle_overlay_pipeline = create_render_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &shader,
+            config.format,
+            triangle_primitive,
+            false,
+            "Triangle Overlay Pipeline",
+        );
+
+        let line_overlay_pipeline = create_render_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &shader,
+            config.format,
+            line_primitive,
+            false,
+            "Line Overlay Pipeline",
+        );
+
+        let line_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Line Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("line.wgsl").into()),
         });
 
+        let thick_line_render_pipeline = create_thick_line_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &line_shader,
+            config.format,
+            true,
+            "Thick Line Render Pipeline",
+        );
+
+        let thick_line_overlay_pipeline = create_thick_line_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &line_shader,
+            config.format,
+            false,
+            "Thick Line Overlay Pipeline",
+        );
+
+        let depth_texture = Texture::create_depth_texture(&device, &config, "depth_texture");
         
         let draw_state = DrawState::new((1., 1., 1.));
 
@@ -280,38 +561,201 @@ impl<'a> App<'a>{
             draw_state,
             triangle_render_pipeline,
             line_render_pipeline,
+            triangle_overlay_pipeline,
+            line_overlay_pipeline,
+            thick_line_render_pipeline,
+            thick_line_overlay_pipeline,
+            depth_texture,
+            #[cfg(not(target_arch = "wasm32"))]
+            offscreen_texture: None,
+            camera,
+            controller: OrbitController::new(),
+            input_state: InputState::new(),
+            clock: Clock::new(),
+            camera_uniform,
+            camera_buffer,
+            camera_bind_group,
+            light_buffer,
+            model_bind_group_layout,
+            model_buffer,
+            model_bind_group,
+            model_capacity,
+            model_stride,
+            uploaded_models: Vec::new(),
+            default_instance_buffer,
         }
     }
 
+    /// The window being drawn to.
+    ///
+    /// Panics when rendering headless; use [`try_window`](App::try_window) for code that
+    /// runs either way.
     pub fn window(&self) -> &Window {
-        &self.window
+        self.window.expect("a headless app has no window")
+    }
+
+    /// The window being drawn to, or `None` when rendering headless.
+    pub fn try_window(&self) -> Option<&Window> {
+        self.window
     }
 
     pub fn device(&self) -> &wgpu::Device {
         &self.device
     }
 
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    pub fn size(&self) -> PhysicalSize<u32> {
+        self.size
+    }
+
+    /// Seconds of simulated time since the app started.
+    pub fn time(&self) -> f32 {
+        self.clock.time()
+    }
+
+    /// Seconds advanced by the current update, the fixed step when a fixed update rate is set.
+    pub fn delta(&self) -> f32 {
+        self.clock.delta()
+    }
+
+    /// Number of frames rendered so far.
+    pub fn frame_count(&self) -> u64 {
+        self.clock.frame_count()
+    }
+
     fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             self.size = new_size;
             self.config.width = new_size.width;
             self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+            if let Some(surface) = &self.surface {
+                surface.configure(&self.device, &self.config);
+            }
+            self.depth_texture = Texture::create_depth_texture(&self.device, &self.config, "depth_texture");
+            self.camera.aspect = new_size.width as f32 / new_size.height as f32;
         }
     }
 
-    fn input(&mut self, _event: &WindowEvent) -> bool {
-        false
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    pub fn orbit_controller_mut(&mut self) -> &mut OrbitController {
+        &mut self.controller
+    }
+
+    pub fn input_state(&self) -> &InputState {
+        &self.input_state
+    }
+
+    pub fn key_pressed(&self, key: KeyCode) -> bool {
+        self.input_state.key_pressed(key)
+    }
+
+    pub fn mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.input_state.mouse_button_pressed(button)
+    }
+
+    /// The cursor position in physical pixels from the top-left of the window.
+    pub fn mouse_position(&self) -> Option<[f32; 2]> {
+        self.input_state.mouse_position()
+    }
+
+    /// The point under the cursor on the plane through the camera target facing the camera.
+    pub fn mouse_world_position(&self) -> Option<Point3<f32>> {
+        let [x, y] = self.mouse_position()?;
+        let ndc_x = 2. * x / self.size.width as f32 - 1.;
+        let ndc_y = 1. - 2. * y / self.size.height as f32;
+        self.camera.unproject(ndc_x, ndc_y)
+    }
+
+    /// Lines scrolled horizontally and vertically since the last frame.
+    pub fn scroll_delta(&self) -> [f32; 2] {
+        self.input_state.scroll_delta()
+    }
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        self.input_state.process_event(event);
+        self.controller.process_event(event, &mut self.camera, self.size.height)
     }
 
     fn _update(&mut self) {}
 
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
+    /// Renders to the window, and if `read_back` also returns the frame as it's presented.
+    fn render(&mut self, read_back: bool) -> Result<Option<Image>, wgpu::SurfaceError> {
+        let output = self.surface.as_ref()
+            .expect("render requires a window surface")
+            .get_current_texture()?;
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
+        self.render_to_view(&view);
+        #[cfg(not(target_arch = "wasm32"))]
+        let image = read_back.then(|| if self.config.usage.contains(wgpu::TextureUsages::COPY_SRC) {
+            Image::from_texture(&self.device, &self.queue, &output.texture)
+        } else {
+            // Some surfaces can't be copied from, so the frame is drawn again offscreen.
+            self.render_offscreen()
+        });
+        // Frames can't be captured on the web, where there's no `capture_frames` to ask for them.
+        #[cfg(target_arch = "wasm32")]
+        let image = {
+            let _ = read_back;
+            None
+        };
+        output.present();
+
+        Ok(image)
+    }
+
+    /// Renders the current draw state into an offscreen texture and reads it back.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn render_offscreen(&mut self) -> Image {
+        let needs_texture = match &self.offscreen_texture {
+            Some(texture) => texture.width() != self.config.width || texture.height() != self.config.height,
+            None => true,
+        };
+        if needs_texture {
+            self.offscreen_texture = Some(self.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Offscreen Texture"),
+                size: wgpu::Extent3d {
+                    width: self.config.width,
+                    height: self.config.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.config.format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            }));
+        }
+
+        let texture = self.offscreen_texture.take().unwrap();
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.render_to_view(&view);
+        let image = Image::from_texture(&self.device, &self.queue, &texture);
+        self.offscreen_texture = Some(texture);
+
+        image
+    }
+
+    fn render_to_view(&mut self, view: &wgpu::TextureView) {
         let (r, g, b) = self.draw_state.background_color();
 
+        self.camera_uniform.update_view_proj(&self.camera);
+        self.camera_uniform.update_viewport(self.config.width, self.config.height);
+        self.queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[self.camera_uniform]));
+        self.queue.write_buffer(&self.light_buffer, 0, bytemuck::cast_slice(&[self.draw_state.lighting().to_uniform()]));
+        self.write_model_uniforms();
+
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         });
@@ -320,7 +764,7 @@ impl<'a> App<'a>{
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color {
@@ -332,24 +776,48 @@ impl<'a> App<'a>{
                         store: wgpu::StoreOp::Store,
                     },
                 })],
-                depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_texture.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
                 occlusion_query_set: None,
                 timestamp_writes: None,
             });
 
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+
             let geometry_list = self.draw_state.geometry_list();
-            // println!("{:?}", self.draw_state.instance_count());
 
-            for geometry in geometry_list.iter() {
-                render_pass.set_pipeline(match geometry.geometry_type() {
-                    GeometryType::Line => &self.line_render_pipeline,
-                    GeometryType::Mesh => &self.triangle_render_pipeline,
-                    
+            // Geometry that writes depth goes first so overlays are tested against the full scene.
+            let ordered = geometry_list.iter().enumerate().filter(|(_, g)| g.depth_write())
+                .chain(geometry_list.iter().enumerate().filter(|(_, g)| !g.depth_write()));
+
+            for (i, geometry) in ordered {
+                if geometry.num_indices() == 0 || geometry.num_instances() == 0 {
+                    continue;
+                }
+                render_pass.set_bind_group(1, &self.model_bind_group, &[(i as u64 * self.model_stride) as u32]);
+                render_pass.set_pipeline(match (geometry.geometry_type(), geometry.depth_write()) {
+                    (GeometryType::Line, true) => &self.line_render_pipeline,
+                    (GeometryType::Mesh, true) => &self.triangle_render_pipeline,
+                    (GeometryType::ThickLine, true) => &self.thick_line_render_pipeline,
+                    (GeometryType::Line, false) => &self.line_overlay_pipeline,
+                    (GeometryType::Mesh, false) => &self.triangle_overlay_pipeline,
+                    (GeometryType::ThickLine, false) => &self.thick_line_overlay_pipeline,
                 });
                 render_pass.set_vertex_buffer(0, geometry.vertex_buffer().slice(..));
+                // Thick lines keep their segments in the vertex buffer, one per instance.
+                if !matches!(geometry.geometry_type(), GeometryType::ThickLine) {
+                    let instance_buffer = geometry.instance_buffer().unwrap_or(&self.default_instance_buffer);
+                    render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+                }
                 // DONT FORGET TO CHANGE THE BELOW WHEN SWITCHING BETWEEN i32 and i16 indices
                 render_pass.set_index_buffer(geometry.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
-                render_pass.draw_indexed(0..geometry.num_indices(), 0, 0..self.draw_state.instance_count());
+                render_pass.draw_indexed(geometry.index_range(), 0, 0..geometry.num_instances());
             }
             // render_pass.set_pipeline(&self.line_render_pipeline); // 2.
             
@@ -358,9 +826,55 @@ impl<'a> App<'a>{
     
         // submit will accept anything that implements IntoIter
         self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
-    
-        Ok(())
+    }
+
+    /// Renders the current draw state and saves it to `path` as a PNG.
+    ///
+    /// Not available on the web, where frames can't be waited on to read them back.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn capture_frame(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        self.render_offscreen().save_png(path)
+    }
+
+    /// Uploads the model matrix of every geometry in the draw state that changed since the
+    /// last frame, growing the buffer if needed.
+    fn write_model_uniforms(&mut self) {
+        let geometry_list = self.draw_state.geometry_list();
+        let count = geometry_list.len() as u64;
+        if count > self.model_capacity {
+            self.model_capacity = count.next_power_of_two();
+            (self.model_buffer, self.model_bind_group) = create_model_buffer(
+                &self.device,
+                &self.model_bind_group_layout,
+                self.model_capacity,
+                self.model_stride,
+            );
+            self.uploaded_models.clear();
+        }
+
+        let stride = self.model_stride as usize;
+        let mut data = vec![0u8; geometry_list.len() * stride];
+        for (slot, geometry) in data.chunks_exact_mut(stride).zip(geometry_list.iter()) {
+            let uniform = ModelUniform::new(geometry.model_matrix(), geometry.screen_space());
+            let bytes = bytemuck::bytes_of(&uniform);
+            slot[..bytes.len()].copy_from_slice(bytes);
+        }
+
+        // Write each run of changed slots at once.
+        let changed = |i: usize| self.uploaded_models.get(i * stride..(i + 1) * stride) != Some(&data[i * stride..(i + 1) * stride]);
+        let mut i = 0;
+        while i < geometry_list.len() {
+            if !changed(i) {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < geometry_list.len() && changed(i) {
+                i += 1;
+            }
+            self.queue.write_buffer(&self.model_buffer, (start * stride) as u64, &data[start * stride..i * stride]);
+        }
+        self.uploaded_models = data;
     }
 
     pub fn draw_state(&mut self) -> &mut DrawState{
@@ -369,7 +883,9 @@ impl<'a> App<'a>{
 
     pub fn draw(&self) -> DrawState{
         let background_color = self.draw_state.background_color();
-        DrawState::new(background_color)
+        let mut draw = DrawState::new(background_color);
+        draw.set_lighting(self.draw_state.lighting().clone());
+        draw
     }
 
     pub fn draw_to_frame(&mut self, draw: DrawState) {
@@ -377,12 +893,186 @@ impl<'a> App<'a>{
     }
 }
 
+async fn request_device(adapter: &wgpu::Adapter) -> (wgpu::Device, wgpu::Queue) {
+    adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            required_features: wgpu::Features::empty(),
+            // WebGL doesn't support all of wgpu's features, so if
+            // we're building for the web, we'll have to disable some. Elsewhere ask
+            // only for what older and software adapters, like those on CI machines,
+            // can give, with the largest textures the adapter allows.
+            required_limits: if cfg!(target_arch = "wasm32") {
+                wgpu::Limits::downlevel_webgl2_defaults()
+            } else {
+                wgpu::Limits::downlevel_defaults()
+            }.using_resolution(adapter.limits()),
+            label: None,
+        },
+        None, // Trace path
+    ).await.unwrap()
+}
+
+fn create_model_buffer(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    capacity: u64,
+    stride: u64,
+) -> (wgpu::Buffer, wgpu::BindGroup) {
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Model Buffer"),
+        size: capacity * stride,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(std::mem::size_of::<ModelUniform>() as u64),
+                }),
+            }
+        ],
+        label: Some("model_bind_group"),
+    });
+
+    (buffer, bind_group)
+}
+
+fn create_render_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    primitive: wgpu::PrimitiveState,
+    depth_write_enabled: bool,
+    label: &str,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main", // 1.
+            buffers: &[Vertex::desc(), InstanceRaw::desc()], // 2.
+        },
+        fragment: Some(wgpu::FragmentState { // 3.
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState { // 4.
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive,
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: Texture::DEPTH_FORMAT,
+            depth_write_enabled,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: 1, // 2.
+            mask: !0, // 3.
+            alpha_to_coverage_enabled: false, // 4.
+        },
+        multiview: None, // 5.
+    })
+}
+
+/// A pipeline for lines of constant width on screen, blended over what's behind them.
+fn create_thick_line_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    depth_write_enabled: bool,
+    label: &str,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[LineSegmentRaw::desc()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                // Keep the target opaque where the edges are blended in.
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::SrcAlpha,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent::OVER,
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        // The quads face either way depending on the direction of the segment.
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: Texture::DEPTH_FORMAT,
+            depth_write_enabled,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+struct Callbacks<M> {
+    update: Option<UpdateFn<M>>,
+    view: Option<ViewFn<M>>,
+    event: Option<EventFn<M>>,
+    fixed_step: Option<f32>,
+}
+
+impl<M> Callbacks<M> {
+    /// Advances the clock by `elapsed` seconds, updating the model once or at the
+    /// fixed rate, then draws it.
+    fn frame(&self, app: &mut App, model: &mut M, elapsed: f32) {
+        match self.fixed_step {
+            Some(step) => {
+                for _ in 0..app.clock.fixed_steps(elapsed, step) {
+                    app.clock.advance(step);
+                    if let Some(update) = self.update {
+                        update(app, model)
+                    }
+                }
+            }
+            None => {
+                app.clock.advance(elapsed);
+                if let Some(update) = self.update {
+                    update(app, model)
+                }
+            }
+        }
+        if let Some(view) = self.view {
+            view(app, model)
+        }
+    }
+}
+
 fn run_loop<M>(
     mut app: App,
     event_loop: EventLoop<()>,
     mut model: M,
-    view: Option<ViewFn<M>>,
-    update: Option<UpdateFn<M>>,
+    callbacks: Callbacks<M>,
+    capture: Option<FrameSequence>,
 ) where
     M: 'static,
 {
@@ -390,7 +1080,10 @@ fn run_loop<M>(
         Event::WindowEvent {
             ref event,
             window_id,
-        } if window_id == app.window.id() => if !app.input(event) {
+        } if Some(window_id) == app.window.map(|w| w.id()) && !app.input(event) => {
+            if let Some(event_fn) = callbacks.event {
+                event_fn(&app, &mut model, event);
+            }
             match event {
                 WindowEvent::CloseRequested
                 | WindowEvent::KeyboardInput {
@@ -406,14 +1099,18 @@ fn run_loop<M>(
                     app.resize(*physical_size);
                 },
                 WindowEvent::RedrawRequested => {
-                    if let Some(update) = update{
-                        update(&app, &mut model)
-                    }
-                    if let Some(view) = view{
-                        view(&mut app, &model)
-                    };
-                    match app.render() {
-                        Ok(_) => {}
+                    let elapsed = app.clock.tick();
+                    callbacks.frame(&mut app, &mut model, elapsed);
+                    let frame = app.frame_count();
+                    let captured = capture.as_ref().is_some_and(|capture| capture.path(frame).is_some());
+                    match app.render(captured) {
+                        Ok(image) => {
+                            if let (Some(capture), Some(image)) = (&capture, image) {
+                                if let Err(e) = capture.save(frame, &image) {
+                                    log::error!("Couldn't save frame {}: {}", frame, e);
+                                }
+                            }
+                        }
                         // Reconfigure the surface if lost
                         Err(wgpu::SurfaceError::Lost) => app.resize(app.size),
                         // The system is out of memory, we should probably quit
@@ -421,6 +1118,8 @@ fn run_loop<M>(
                         // All other errors (Outdated, Timeout) should be resolved by the next frame
                         Err(e) => eprintln!("{:?}", e),
                     }
+                    app.clock.end_frame();
+                    app.input_state.end_frame();
                 },
                 // WindowEvent::CursorMoved { device_id: _, position, modifiers: _ } => {
                 //    app.draw_state.update_background_color((position.x/app.size.width as f64, 0., position.y/app.size.height as f64));
@@ -429,7 +1128,9 @@ fn run_loop<M>(
             }
         },
         Event::AboutToWait => {
-            app.window.request_redraw();
+            if let Some(window) = app.window {
+                window.request_redraw();
+            }
         }
 
         _ => {}
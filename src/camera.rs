@@ -0,0 +1,133 @@
+use cgmath::{Matrix4, Point3, Vector3, Vector4, Deg, SquareMatrix, InnerSpace};
+
+// wgpu's normalized device coordinates have z in [0, 1] whereas cgmath builds
+// OpenGL style matrices with z in [-1, 1], so we squash z after projecting.
+#[rustfmt::skip]
+pub const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// Perspective projection with a vertical field of view.
+    Perspective { fovy: Deg<f32> },
+    /// Orthographic projection showing `height` world units vertically.
+    Orthographic { height: f32 },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    pub eye: Point3<f32>,
+    pub target: Point3<f32>,
+    pub up: Vector3<f32>,
+    pub aspect: f32,
+    pub projection: Projection,
+    pub znear: f32,
+    pub zfar: f32,
+}
+
+impl Camera {
+    pub fn new(aspect: f32) -> Camera {
+        Camera {
+            eye: (0., -2., 1.).into(),
+            target: (0., 0., 0.).into(),
+            up: Vector3::unit_z(),
+            aspect,
+            projection: Projection::Perspective { fovy: Deg(45.) },
+            znear: 0.1,
+            zfar: 100.,
+        }
+    }
+
+    pub fn perspective(&mut self, fovy: f32) -> &mut Camera {
+        self.projection = Projection::Perspective { fovy: Deg(fovy) };
+        self
+    }
+
+    pub fn orthographic(&mut self, height: f32) -> &mut Camera {
+        self.projection = Projection::Orthographic { height };
+        self
+    }
+
+    pub fn look_at(&mut self, eye: impl Into<Point3<f32>>, target: impl Into<Point3<f32>>) -> &mut Camera {
+        self.eye = eye.into();
+        self.target = target.into();
+        self
+    }
+
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        Matrix4::look_at_rh(self.eye, self.target, self.up)
+    }
+
+    pub fn projection_matrix(&self) -> Matrix4<f32> {
+        let proj = match self.projection {
+            Projection::Perspective { fovy } => cgmath::perspective(fovy, self.aspect, self.znear, self.zfar),
+            Projection::Orthographic { height } => {
+                let half_h = height / 2.;
+                let half_w = half_h * self.aspect;
+                cgmath::ortho(-half_w, half_w, -half_h, half_h, self.znear, self.zfar)
+            }
+        };
+        OPENGL_TO_WGPU_MATRIX * proj
+    }
+
+    pub fn view_projection_matrix(&self) -> Matrix4<f32> {
+        self.projection_matrix() * self.view_matrix()
+    }
+
+    /// The world space point under normalized device coordinates `(x, y)` on the
+    /// plane through the target that faces the camera.
+    pub fn unproject(&self, x: f32, y: f32) -> Option<Point3<f32>> {
+        let inverse = self.view_projection_matrix().invert()?;
+        let near = Point3::from_homogeneous(inverse * Vector4::new(x, y, 0., 1.));
+        let far = Point3::from_homogeneous(inverse * Vector4::new(x, y, 1., 1.));
+        let direction = far - near;
+
+        let normal = (self.target - self.eye).normalize();
+        let denominator = direction.dot(normal);
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+        let t = (self.target - near).dot(normal) / denominator;
+        Some(near + direction * t)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CameraUniform {
+    view_position: [f32; 4],
+    view_proj: [[f32; 4]; 4],
+    viewport: [f32; 2],
+    _padding: [f32; 2],
+}
+
+impl CameraUniform {
+    pub fn new() -> CameraUniform {
+        CameraUniform {
+            view_position: [0.; 4],
+            view_proj: Matrix4::identity().into(),
+            viewport: [1., 1.],
+            _padding: [0.; 2],
+        }
+    }
+
+    pub fn update_view_proj(&mut self, camera: &Camera) {
+        self.view_position = camera.eye.to_homogeneous().into();
+        self.view_proj = camera.view_projection_matrix().into();
+    }
+
+    /// Sets the size in pixels of the target being rendered to, which wide lines are measured in.
+    pub fn update_viewport(&mut self, width: u32, height: u32) {
+        self.viewport = [width as f32, height as f32];
+    }
+}
+
+impl Default for CameraUniform {
+    fn default() -> CameraUniform {
+        CameraUniform::new()
+    }
+}
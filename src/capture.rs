@@ -0,0 +1,140 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// RGBA8 pixel data read back from a rendered frame, stored row by row from the top.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    /// Returns the RGBA value of the pixel at `(x, y)`, with `(0, 0)` the top-left corner.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        [self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]]
+    }
+
+    /// Writes the image to `path` as an 8 bit sRGB PNG.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let file = File::create(path)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.data)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Copies `texture` into a mapped staging buffer and waits for the GPU to finish.
+    ///
+    /// The texture must have been created with `COPY_SRC` and use an 8 bit RGBA or BGRA format.
+    /// On the web the GPU can't be waited on, so there's no way to read a texture back.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn from_texture(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) -> Image {
+        let width = texture.width();
+        let height = texture.height();
+
+        // Rows of a texture copy must be aligned to 256 bytes, so the staging
+        // buffer may be wider than the image.
+        let unpadded_bytes_per_row = width * 4;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Readback Encoder"),
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv().unwrap().expect("Couldn't map the readback buffer");
+
+        let mut data = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        {
+            let mapped = slice.get_mapped_range();
+            for row in mapped.chunks(padded_bytes_per_row as usize) {
+                data.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        buffer.unmap();
+
+        if matches!(texture.format(), wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb) {
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        Image { width, height, data }
+    }
+}
+
+/// Where and how often [`AppBuilder::capture_frames`](crate::app::AppBuilder::capture_frames)
+/// writes numbered PNG frames.
+#[derive(Clone, Debug)]
+pub struct FrameSequence {
+    /// Saves every `every`th frame; 0 saves every frame, like 1.
+    pub every: u32,
+    pub directory: PathBuf,
+}
+
+impl FrameSequence {
+    pub fn new(every: u32, directory: impl Into<PathBuf>) -> FrameSequence {
+        FrameSequence { every, directory: directory.into() }
+    }
+
+    /// The file frame number `frame` should be saved to, if it is part of the sequence.
+    ///
+    /// Files are numbered by their position in the sequence rather than by frame so
+    /// that tools like ffmpeg can read them without gaps.
+    pub fn path(&self, frame: u64) -> Option<PathBuf> {
+        let every = self.every.max(1) as u64;
+        if !frame.is_multiple_of(every) {
+            return None;
+        }
+        Some(self.directory.join(format!("frame_{:05}.png", frame / every)))
+    }
+
+    pub fn save(&self, frame: u64, image: &Image) -> std::io::Result<()> {
+        if let Some(path) = self.path(frame) {
+            std::fs::create_dir_all(&self.directory)?;
+            image.save_png(path)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+/// Maps scalars in `[0, 1]` onto colors by interpolating between color stops.
+///
+/// Stops are given in sRGB, as colormaps are usually published, and interpolated in
+/// sRGB. Mapped colors are converted to the linear values vertex colors are in, so
+/// they show up on screen as the colormap intends.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Colormap {
+    stops: Vec<(f32, [f32; 3])>,
+}
+
+impl Colormap {
+    /// A colormap through the given `(position, sRGB color)` stops. Positions outside
+    /// `[0, 1]` are clamped and the stops are sorted by position.
+    pub fn new(stops: Vec<(f32, [f32; 3])>) -> Colormap {
+        assert!(!stops.is_empty(), "a colormap needs at least one color");
+        let mut stops: Vec<_> = stops.into_iter().map(|(t, color)| (t.clamp(0., 1.), color)).collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Colormap { stops }
+    }
+
+    /// A colormap through evenly spaced sRGB colors.
+    pub fn gradient(colors: Vec<[f32; 3]>) -> Colormap {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        Colormap::new(colors.into_iter().enumerate().map(|(i, color)| (i as f32 / last, color)).collect())
+    }
+
+    fn from_hex(colors: &[u32]) -> Colormap {
+        Colormap::gradient(colors.iter().map(|hex| {
+            [hex >> 16, hex >> 8, *hex].map(|channel| (channel & 0xff) as f32 / 255.)
+        }).collect())
+    }
+
+    /// Perceptually uniform dark blue to yellow, matplotlib's default.
+    pub fn viridis() -> Colormap {
+        Colormap::from_hex(&[0x440154, 0x472d7b, 0x3b528b, 0x2c728e, 0x21918c, 0x28ae80, 0x5ec962, 0xaddc30, 0xfde725])
+    }
+
+    /// Perceptually uniform dark blue to yellow through purple and orange.
+    pub fn plasma() -> Colormap {
+        Colormap::from_hex(&[0x0d0887, 0x4c02a1, 0x7e03a8, 0xa92395, 0xcc4778, 0xe56b5d, 0xf89540, 0xfdc527, 0xf0f921])
+    }
+
+    /// Diverging blue to red through light gray, for values on either side of a midpoint.
+    pub fn coolwarm() -> Colormap {
+        Colormap::from_hex(&[0x3b4cc0, 0x8db0fe, 0xdddddd, 0xf4987a, 0xb40426])
+    }
+
+    pub fn grayscale() -> Colormap {
+        Colormap::gradient(vec![[0., 0., 0.], [1., 1., 1.]])
+    }
+
+    /// The linear color at `t`, clamped to `[0, 1]`.
+    pub fn map(&self, t: f32) -> [f32; 3] {
+        srgb_to_linear(self.map_srgb(t))
+    }
+
+    /// The linear color of `value` on a scale from `min` to `max`.
+    pub fn map_range(&self, value: f32, min: f32, max: f32) -> [f32; 3] {
+        let t = if max > min { (value - min) / (max - min) } else { 0.5 };
+        self.map(t)
+    }
+
+    fn map_srgb(&self, t: f32) -> [f32; 3] {
+        let t = if t.is_nan() { 0. } else { t.clamp(0., 1.) };
+        let next = self.stops.iter().position(|&(position, _)| position >= t).unwrap_or(self.stops.len() - 1);
+        if next == 0 {
+            return self.stops[0].1;
+        }
+        let ((t0, c0), (t1, c1)) = (self.stops[next - 1], self.stops[next]);
+        let f = if t1 > t0 { (t - t0) / (t1 - t0) } else { 1. };
+        [0, 1, 2].map(|k| c0[k] + (c1[k] - c0[k]) * f)
+    }
+}
+
+impl Default for Colormap {
+    fn default() -> Colormap {
+        Colormap::viridis()
+    }
+}
+
+/// The smallest and largest finite value, or `[0, 1]` if none are finite.
+pub fn finite_range(values: &[f32]) -> [f32; 2] {
+    let [min, max] = values.iter()
+        .filter(|v| v.is_finite())
+        .fold([f32::INFINITY, f32::NEG_INFINITY], |[min, max], &v| [min.min(v), max.max(v)]);
+    if min <= max { [min, max] } else { [0., 1.] }
+}
+
+fn srgb_to_linear(color: [f32; 3]) -> [f32; 3] {
+    color.map(|c| if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) })
+}
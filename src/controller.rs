@@ -0,0 +1,183 @@
+use cgmath::{InnerSpace, Quaternion, Rad, Rotation, Rotation3, Vector3};
+use winit::{
+    dpi::PhysicalPosition,
+    event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::camera::{Camera, Projection};
+
+/// Mouse driven camera controls: left-drag orbits around the target, right-drag
+/// (or middle-drag) pans, the scroll wheel zooms and `R` restores the camera to
+/// where it was before the first interaction.
+pub struct OrbitController {
+    pub enabled: bool,
+    /// Radians of rotation per pixel dragged.
+    pub rotate_speed: f32,
+    /// Fraction the distance to the target shrinks by per line scrolled.
+    pub zoom_speed: f32,
+    orbiting: bool,
+    panning: bool,
+    cursor: Option<PhysicalPosition<f64>>,
+    home: Option<Camera>,
+}
+
+impl OrbitController {
+    pub fn new() -> OrbitController {
+        OrbitController {
+            enabled: true,
+            rotate_speed: 0.01,
+            zoom_speed: 0.1,
+            orbiting: false,
+            panning: false,
+            cursor: None,
+            home: None,
+        }
+    }
+
+    /// Updates `camera` from `event`, returning whether the event was used.
+    pub fn process_event(&mut self, event: &WindowEvent, camera: &mut Camera, viewport_height: u32) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        match event {
+            WindowEvent::MouseInput { state, button, .. } => {
+                let pressed = *state == ElementState::Pressed;
+                match button {
+                    MouseButton::Left => self.orbiting = pressed,
+                    MouseButton::Right | MouseButton::Middle => self.panning = pressed,
+                    _ => return false,
+                }
+                true
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let last = self.cursor.replace(*position);
+                let Some(last) = last else {
+                    return false;
+                };
+                let dx = (position.x - last.x) as f32;
+                let dy = (position.y - last.y) as f32;
+                if self.orbiting {
+                    self.orbit(camera, dx, dy);
+                    true
+                } else if self.panning {
+                    self.pan(camera, dx, dy, viewport_height);
+                    true
+                } else {
+                    false
+                }
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                false
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 50.,
+                };
+                self.zoom(camera, lines);
+                true
+            }
+            WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    state: ElementState::Pressed,
+                    physical_key: PhysicalKey::Code(KeyCode::KeyR),
+                    ..
+                },
+                ..
+            } => {
+                self.reset(camera);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Restores the camera to where it was before it was first moved by the controller.
+    pub fn reset(&mut self, camera: &mut Camera) {
+        if let Some(home) = self.home {
+            // Keep the aspect ratio of the current window.
+            *camera = Camera { aspect: camera.aspect, ..home };
+        }
+    }
+
+    fn save_home(&mut self, camera: &Camera) {
+        if self.home.is_none() {
+            self.home = Some(*camera);
+        }
+    }
+
+    /// Rotates the eye around the target as a drag of `dx` by `dy` pixels would.
+    pub fn orbit(&mut self, camera: &mut Camera, dx: f32, dy: f32) {
+        self.save_home(camera);
+        let up = camera.up.normalize();
+        let offset = camera.eye - camera.target;
+
+        let yaw = Quaternion::from_axis_angle(up, Rad(-dx * self.rotate_speed));
+        let offset = yaw.rotate_vector(offset);
+
+        // Positive rotations about this axis tilt the eye towards the up axis. Stop
+        // just short of it, where the view direction would flip.
+        let axis = perpendicular(offset, up);
+        let polar = offset.angle(up).0;
+        let pitch = (dy * self.rotate_speed).clamp(polar - std::f32::consts::PI + 1e-3, polar - 1e-3);
+        let offset = Quaternion::from_axis_angle(axis, Rad(pitch)).rotate_vector(offset);
+
+        camera.eye = camera.target + offset;
+    }
+
+    /// Moves the eye and the target sideways as a drag of `dx` by `dy` pixels would, so
+    /// the point under the cursor follows it.
+    pub fn pan(&mut self, camera: &mut Camera, dx: f32, dy: f32, viewport_height: u32) {
+        self.save_home(camera);
+        let forward = camera.target - camera.eye;
+        let right = perpendicular(forward, camera.up);
+        let up = right.cross(forward).normalize();
+
+        // World units covered by one pixel at the target's depth.
+        let visible_height = match camera.projection {
+            Projection::Perspective { fovy } => 2. * forward.magnitude() * (Rad::from(fovy).0 / 2.).tan(),
+            Projection::Orthographic { height } => height,
+        };
+        let scale = visible_height / viewport_height.max(1) as f32;
+
+        let delta: Vector3<f32> = (-dx * right + dy * up) * scale;
+        camera.eye += delta;
+        camera.target += delta;
+    }
+
+    /// Moves the eye towards the target, or shrinks an orthographic view, as scrolling
+    /// `lines` would.
+    pub fn zoom(&mut self, camera: &mut Camera, lines: f32) {
+        self.save_home(camera);
+        let factor = (1. - self.zoom_speed).powf(lines);
+        match &mut camera.projection {
+            Projection::Perspective { .. } => {
+                let offset = (camera.eye - camera.target) * factor;
+                if offset.magnitude() > camera.znear {
+                    camera.eye = camera.target + offset;
+                }
+            }
+            Projection::Orthographic { height } => *height *= factor,
+        }
+    }
+}
+
+/// The unit vector along `v` × `up`, or some other one perpendicular to `up` when `v`
+/// is parallel to it, like the view direction of a camera looking straight down.
+fn perpendicular(v: Vector3<f32>, up: Vector3<f32>) -> Vector3<f32> {
+    let axis = v.cross(up);
+    if axis.magnitude2() > 1e-12 * v.magnitude2() * up.magnitude2() {
+        return axis.normalize();
+    }
+    let other = if up.x.abs() < up.y.abs() { Vector3::unit_x() } else { Vector3::unit_y() };
+    other.cross(up).normalize()
+}
+
+impl Default for OrbitController {
+    fn default() -> OrbitController {
+        OrbitController::new()
+    }
+}
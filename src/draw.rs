@@ -1,32 +1,42 @@
-use crate::{geometry::{Geometry, Draw}, app::App};
+use crate::{geometry::{Geometry, Draw}, app::App, instance::Instance, light::{Light, Lighting}, scene::Scene};
 
 pub type Color = (f64, f64, f64);
 
 pub struct DrawState {
     background_color: Color,
     geometry_list: Vec<Geometry>,
-    instance_count: u32,
+    lighting: Lighting,
 }
 
 impl DrawState {
     pub fn new(background_color: Color) -> DrawState {
         let geometry_list: Vec<Geometry> = Vec::new();
-        let instance_count = geometry_list.len() as u32;
         DrawState{
             background_color,
             geometry_list,
-            instance_count,
+            lighting: Lighting::new(),
         }
     }
 
-    pub fn add_geometry(&mut self, geometry: Geometry) {
+    pub fn add_geometry(&mut self, geometry: Geometry) -> &mut Geometry {
         self.geometry_list.push(geometry);
-        self.instance_count = self.geometry_list.len() as u32;
+        self.geometry_list.last_mut().unwrap()
     }
 
-    pub fn add(&mut self, object: &dyn Draw, app: &App) {
-        self.geometry_list.push(object.draw(app));
-        self.instance_count = self.geometry_list.len() as u32;
+    pub fn add(&mut self, object: &dyn Draw, app: &App) -> &mut Geometry {
+        self.add_geometry(object.draw(app))
+    }
+
+    /// Draws `object` once per instance in a single draw call.
+    pub fn add_instanced(&mut self, object: &dyn Draw, instances: &[Instance], app: &App) -> &mut Geometry {
+        let mut geometry = object.draw(app);
+        geometry.set_instances(app, instances);
+        self.add_geometry(geometry)
+    }
+
+    /// Draws every visible node of `scene`, sharing the buffers it already uploaded.
+    pub fn add_scene(&mut self, scene: &Scene) {
+        self.geometry_list.extend(scene.visible_geometry());
     }
 
     pub fn update_background_color(&mut self, color: Color) {
@@ -41,7 +51,30 @@ impl DrawState {
         &self.geometry_list
     }
 
-    pub fn instance_count(&self) -> u32 {
-        self.instance_count
+    pub fn lighting(&self) -> &Lighting {
+        &self.lighting
+    }
+
+    pub fn set_lighting(&mut self, lighting: Lighting) {
+        self.lighting = lighting;
+    }
+
+    pub fn set_ambient_light(&mut self, color: [f32; 3]) {
+        self.lighting.ambient = color;
     }
+
+    pub fn add_light(&mut self, light: Light) {
+        self.lighting.lights.push(light);
+    }
+
+    /// Removes all directional and point lights, leaving only the ambient term.
+    pub fn clear_lights(&mut self) {
+        self.lighting.lights.clear();
+    }
+
+    pub fn set_specular(&mut self, strength: f32, shininess: f32) {
+        self.lighting.specular = strength;
+        self.lighting.shininess = shininess;
+    }
+
 }
\ No newline at end of file
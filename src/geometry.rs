@@ -1,38 +1,113 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Arc;
+
 use wgpu::util::DeviceExt;
 
-use crate::vertex::Vertex;
+use cgmath::{InnerSpace, Matrix4, Quaternion, SquareMatrix, Vector3};
+
+use crate::vertex::{self, Vertex};
 use crate::app::App;
+use crate::transform::Transform;
+use crate::instance::Instance;
+
+pub mod adaptive;
+pub mod coloring;
+pub mod curve;
+pub mod domain;
+pub mod dynamic;
+pub mod implicit;
+pub mod line;
+pub mod mesh;
+pub mod obj;
+pub mod plot;
+pub mod primitives;
+pub mod stl;
 
+pub use adaptive::{Adaptive, Tolerance};
+pub use coloring::Coloring;
+pub use curve::ParametricCurve;
+pub use domain::{Boundary, Domain};
+pub use dynamic::{DynamicGeometry, DynamicSlots};
+pub use implicit::ImplicitSurface;
+pub use line::{LineCap, LineJoin, LineStyle};
+pub use mesh::{BoundingBox, Mesh, MeshError};
+pub use plot::{Colorbar, SurfacePlot};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum GeometryType {
     Line,
     Mesh,
+    /// A line drawn with a [`LineStyle`]. The vertex buffer holds one
+    /// [`LineSegmentRaw`](line::LineSegmentRaw) per segment, drawn as an instance of a quad.
+    ThickLine,
 }
 
+/// Buffers uploaded to the GPU and how to draw them.
+///
+/// Cloning a geometry shares its buffers rather than uploading them again, so the same
+/// geometry can be drawn in several places or kept across frames.
+#[derive(Clone)]
 pub struct Geometry{
     geometry_type: GeometryType,
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
+    vertex_buffer: Arc<wgpu::Buffer>,
+    index_buffer: Arc<wgpu::Buffer>,
+    first_index: u32,
     num_indices: u32,
+    depth_write: bool,
+    screen_space: bool,
+    transform: Transform,
+    parent: Matrix4<f32>,
+    instance_buffer: Option<Arc<wgpu::Buffer>>,
+    num_instances: u32,
 }
 
 //TODO: This is confusing to have in the geometry module and should be moved to the geometry module next commit.
 pub trait Draw{ 
     fn draw(&self, app: &App) -> Geometry;
+
+    /// The triangles that [`draw`](Draw::draw) uploads, kept on the CPU, or `None` for
+    /// objects that aren't made of triangles.
+    fn mesh(&self) -> Option<Mesh> {
+        None
+    }
+
+    /// Whether the object changes with [`App::time`], so what [`draw`](Draw::draw) uploads
+    /// is only right for the frame it's drawn in.
+    fn is_animated(&self) -> bool {
+        false
+    }
 }
 
 impl Geometry{
     pub fn new_mesh(
         app: &App,
-        vertices: &Vec<Vertex>,
+        vertices: &[Vertex],
         indices: &[u32],
     ) -> Geometry {
         let (vertex_buffer, index_buffer, num_indices) = Geometry::buffers_from_slice(app, vertices, indices);
+        Geometry::from_buffers(GeometryType::Mesh, vertex_buffer, index_buffer, 0..num_indices)
+    }
 
+    /// A geometry drawing `indices` out of buffers that are already on the GPU.
+    pub(crate) fn from_buffers(
+        geometry_type: GeometryType,
+        vertex_buffer: Arc<wgpu::Buffer>,
+        index_buffer: Arc<wgpu::Buffer>,
+        indices: Range<u32>,
+    ) -> Geometry {
         Geometry {
-            geometry_type: GeometryType::Mesh,
+            geometry_type,
             vertex_buffer,
             index_buffer,
-            num_indices,
+            first_index: indices.start,
+            num_indices: indices.len() as u32,
+            depth_write: true,
+            screen_space: false,
+            transform: Transform::new(),
+            parent: Matrix4::identity(),
+            instance_buffer: None,
+            num_instances: 1,
         }
     }
 
@@ -52,22 +127,139 @@ impl Geometry{
         self.num_indices
     }
 
+    /// The part of the index buffer that is drawn.
+    pub fn index_range(&self) -> Range<u32> {
+        self.first_index..self.first_index + self.num_indices
+    }
+
+    pub fn depth_write(&self) -> bool {
+        self.depth_write
+    }
+
+    /// Enables or disables depth writes, e.g. for overlays that should not occlude the scene.
+    pub fn set_depth_write(&mut self, depth_write: bool) -> &mut Geometry {
+        self.depth_write = depth_write;
+        self
+    }
+
+    pub fn screen_space(&self) -> bool {
+        self.screen_space
+    }
+
+    /// Treats positions, after the geometry's transform, as normalized device coordinates
+    /// instead of world coordinates, so the geometry stays put on screen whatever the camera
+    /// does. `(-1, -1)` is the bottom left corner of the window and `(1, 1)` the top right.
+    pub fn set_screen_space(&mut self, screen_space: bool) -> &mut Geometry {
+        self.screen_space = screen_space;
+        self
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) -> &mut Geometry {
+        self.transform = transform;
+        self
+    }
+
+    /// The matrix applied after the geometry's own transform, such as the placement of the
+    /// [`Scene`](crate::scene::Scene) node it hangs from.
+    pub fn parent_matrix(&self) -> Matrix4<f32> {
+        self.parent
+    }
+
+    pub fn set_parent_matrix(&mut self, parent: Matrix4<f32>) -> &mut Geometry {
+        self.parent = parent;
+        self
+    }
+
+    /// The parent matrix followed by the geometry's own transform.
+    pub fn model_matrix(&self) -> Matrix4<f32> {
+        self.parent * self.transform.matrix()
+    }
+
+    pub fn translate(&mut self, translation: impl Into<Vector3<f32>>) -> &mut Geometry {
+        self.transform.translate(translation);
+        self
+    }
+
+    pub fn rotate(&mut self, rotation: impl Into<Quaternion<f32>>) -> &mut Geometry {
+        self.transform.rotate(rotation);
+        self
+    }
+
+    pub fn scale(&mut self, scale: impl Into<Vector3<f32>>) -> &mut Geometry {
+        self.transform.scale(scale);
+        self
+    }
+
+    /// The per-instance buffer, or `None` if the geometry is drawn once.
+    pub fn instance_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.instance_buffer.as_deref()
+    }
+
+    pub fn num_instances(&self) -> u32 {
+        self.num_instances
+    }
+
+    /// Draws one copy of the geometry per instance in a single draw call.
+    ///
+    /// Each instance transform is applied before the geometry's own transform. Without any
+    /// instances nothing is drawn. Thick lines already use instancing for their segments,
+    /// so they're left as they are, with a warning.
+    pub fn set_instances(&mut self, app: &App, instances: &[Instance]) -> &mut Geometry {
+        if matches!(self.geometry_type, GeometryType::ThickLine) {
+            log::warn!("Thick lines can't be instanced, drawing the line once");
+            return self;
+        }
+        if instances.is_empty() {
+            // wgpu can't bind an empty vertex buffer, and there's nothing to draw anyway.
+            self.instance_buffer = None;
+            self.num_instances = 0;
+            return self;
+        }
+        let raw: Vec<_> = instances.iter().map(Instance::to_raw).collect();
+        self.instance_buffer = Some(Arc::new(app.device().create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Instance Buffer"),
+                contents: bytemuck::cast_slice(&raw),
+                usage: wgpu::BufferUsages::VERTEX,
+            }
+        )));
+        self.num_instances = instances.len() as u32;
+        self
+    }
+
     pub fn new_line(
         app: &App,
         vertices: &[Vertex],
         indices: &[u32],
     ) -> Geometry {
         let (vertex_buffer, index_buffer, num_indices) = Geometry::buffers_from_slice(app, vertices, indices);
+        Geometry::from_buffers(GeometryType::Line, vertex_buffer, index_buffer, 0..num_indices)
+    }
 
-        Geometry {
-            geometry_type: GeometryType::Line,
-            vertex_buffer,
-            index_buffer,
-            num_indices,
-        }
+    /// A line through `vertices` drawn with `style`, back to the first vertex if `closed`.
+    ///
+    /// The line is blended over the scene without writing depth, so it's drawn after
+    /// the geometry that does.
+    pub fn new_thick_line(
+        app: &App,
+        vertices: &[Vertex],
+        closed: bool,
+        style: &LineStyle,
+    ) -> Geometry {
+        let segments = style.segments(vertices, closed);
+        // A quad per segment; the line shader places its corners.
+        let (vertex_buffer, index_buffer, num_indices) = Geometry::buffers_from_slice(app, &segments, &[0, 1, 2, 0, 2, 3]);
+        let mut geometry = Geometry::from_buffers(GeometryType::ThickLine, vertex_buffer, index_buffer, 0..num_indices);
+        geometry.depth_write = false;
+        geometry.num_instances = segments.len() as u32;
+        geometry
     }
 
-    fn buffers_from_slice(app: &App, vertices: &[Vertex], indices: &[u32]) -> (wgpu::Buffer, wgpu::Buffer, u32) {
+    fn buffers_from_slice<V: bytemuck::Pod>(app: &App, vertices: &[V], indices: &[u32]) -> (Arc<wgpu::Buffer>, Arc<wgpu::Buffer>, u32) {
         let vertex_buffer = app.device().create_buffer_init(
             &wgpu::util::BufferInitDescriptor {
                 label: Some("Vertex Buffer"),
@@ -85,7 +277,7 @@ impl Geometry{
                 usage: wgpu::BufferUsages::INDEX,
             }
         );
-        return (vertex_buffer, index_buffer, num_indices);
+        (Arc::new(vertex_buffer), Arc::new(index_buffer), num_indices)
     }
 }
 
@@ -97,97 +289,443 @@ impl Polygon{
     pub fn new(vertices: Vec<Vertex>) -> Polygon {
        Polygon { vertices } 
     }
-}
 
-impl Draw for Polygon{
-    fn draw(&self, app: &App) -> Geometry {
+    /// The polygon as a fan of triangles around its first vertex.
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
         let n = self.vertices.len() as u32;
         let mut indices: Vec<u32> = Vec::new();
-        for i in 1..(n-1) {
+        for i in 1..n.saturating_sub(1) {
             indices.push(0);
             indices.push(i);
             indices.push(i+1)
         }
-        Geometry::new_mesh(app, &self.vertices, &indices)
+        let mut vertices = self.vertices.clone();
+        vertex::compute_normals(&mut vertices, &indices);
+        (vertices, indices)
+    }
+}
+
+impl Draw for Polygon{
+    fn draw(&self, app: &App) -> Geometry {
+        let (vertices, indices) = self.tessellate();
+        Geometry::new_mesh(app, &vertices, &indices)
+    }
+
+    fn mesh(&self) -> Option<Mesh> {
+        let (vertices, indices) = self.tessellate();
+        Some(Mesh::from_vertices(&vertices, &indices))
     }
 }
 
-pub type Parametric = fn(a: f32, b: f32) -> [f32; 3];
+/// A surface point as a function of the two surface parameters and the app time.
+pub type Parametric = Box<dyn Fn(f32, f32, f32) -> [f32; 3] + Send + Sync>;
 
 pub struct ParametricSurface {
     p: Parametric,
+    animated: bool,
     r1: Vec<f32>,
     r2: Vec<f32>,
+    b1: Boundary,
+    b2: Boundary,
+    adaptive: Option<Adaptive>,
+    coloring: Coloring,
 }
 
 impl ParametricSurface{
-    pub fn new(p: Parametric, r1: Vec<f32>, r2: Vec<f32>) -> ParametricSurface {
-        ParametricSurface {p, r1, r2}
+    /// A surface sampled over the two parameter domains, whose boundaries decide how
+    /// the ends of each parameter are joined.
+    pub fn new<F>(p: F, r1: impl Into<Domain>, r2: impl Into<Domain>) -> ParametricSurface
+    where
+        F: Fn(f32, f32) -> [f32; 3] + Send + Sync + 'static,
+    {
+        ParametricSurface::animated(move |a, b, _| p(a, b), r1, r2).static_surface()
     }
-}
 
-impl Draw for ParametricSurface{
-    fn draw(&self, app: &App) -> Geometry {
+    /// A surface that also depends on time, passed as the third argument in seconds.
+    /// It is re-tessellated from `App::time` every time it is drawn.
+    pub fn animated<F>(p: F, r1: impl Into<Domain>, r2: impl Into<Domain>) -> ParametricSurface
+    where
+        F: Fn(f32, f32, f32) -> [f32; 3] + Send + Sync + 'static,
+    {
+        let (r1, r2) = (r1.into(), r2.into());
+        ParametricSurface {
+            p: Box::new(p),
+            animated: true,
+            r1: r1.values(),
+            r2: r2.values(),
+            b1: r1.boundary(),
+            b2: r2.boundary(),
+            adaptive: None,
+            coloring: Coloring::default(),
+        }
+    }
+
+    fn static_surface(mut self) -> ParametricSurface {
+        self.animated = false;
+        self
+    }
+
+    /// Overrides how the ends of the first and second parameter are joined.
+    pub fn boundaries(mut self, b1: Boundary, b2: Boundary) -> ParametricSurface {
+        self.b1 = b1;
+        self.b2 = b2;
+        self
+    }
+
+    /// Refines the sampled grid where it doesn't follow the surface closely enough.
+    pub fn adaptive(mut self, adaptive: Adaptive) -> ParametricSurface {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    /// Colors the surface by its parameters, position or a scalar, or in a single color.
+    pub fn coloring(mut self, coloring: impl Into<Coloring>) -> ParametricSurface {
+        self.coloring = coloring.into();
+        self
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.animated
+    }
+
+    /// The unit normal at `(a, b)` and time `t`, from the cross product of the partial derivatives.
+    ///
+    /// Returns `None` where the surface is singular, e.g. at the poles of a sphere.
+    fn normal(&self, a: f32, b: f32, t: f32) -> Option<[f32; 3]> {
+        const H: f32 = 1e-3;
+        let p = |a, b| Vector3::from((self.p)(a, b, t));
+        let da = (p(a + H, b) - p(a - H, b)) / (2. * H);
+        let db = (p(a, b + H) - p(a, b - H)) / (2. * H);
+        let n = da.cross(db);
+        if n.magnitude2() > 1e-12 {
+            Some(n.normalize().into())
+        } else {
+            None
+        }
+    }
+
+    /// Samples the surface at time `t` into an indexed triangle list, counter-clockwise
+    /// when looking against the normal given by the first parameter's derivative crossed
+    /// with the second's.
+    ///
+    /// Without adaptive settings every cell of the parameter grid becomes two triangles.
+    /// With them, cells are refined as a quadtree and a cell next to finer ones is fanned
+    /// from its center through the neighbours' vertices on its edges, so there are no cracks.
+    pub fn tessellate(&self, t: f32) -> (Vec<Vertex>, Vec<u32>) {
+        let n1 = self.r1.len();
+        let n2 = self.r2.len();
+        if n1 < 2 || n2 < 2 {
+            return (Vec::new(), Vec::new());
+        }
+
+        let positions: Vec<[f32; 3]> = self.r1.iter()
+            .flat_map(|&s1| self.r2.iter().map(move |&s2| (s1, s2)))
+            .map(|(s1, s2)| (self.p)(s1, s2, t))
+            .collect();
+        let at = |i: usize, j: usize| positions[i * n2 + j];
+
+        // Distinct samples along each parameter once a duplicated periodic endpoint is welded.
+        let cols = if self.b1 == Boundary::Periodic && (0..n2).all(|j| coincident(at(0, j), at(n1 - 1, j))) {
+            n1 - 1
+        } else {
+            n1
+        };
+        let rows = if self.b2 == Boundary::Periodic && (0..n1).all(|i| coincident(at(i, 0), at(i, n2 - 1))) {
+            n2 - 1
+        } else {
+            n2
+        };
+        let cells1 = if self.b1 == Boundary::Periodic { cols } else { n1 - 1 };
+        let cells2 = if self.b2 == Boundary::Periodic { rows } else { n2 - 1 };
+
+        // Quadtree nodes live on a grid `scale` times finer than the samples, and
+        // vertices are keyed by their coordinates on it.
+        // The field is public, so it may not have been clamped by the builder.
+        let depth = self.adaptive.map_or(0, |adaptive| adaptive.max_depth.min(adaptive::MAX_DEPTH));
+        let scale = 1 << depth;
+        let grid = GridWeld {
+            n1: (n1 - 1) * scale + 1,
+            n2: (n2 - 1) * scale + 1,
+            cols: cols * scale,
+            rows: rows * scale,
+            b1: self.b1,
+            b2: self.b2,
+        };
+        let s1 = |i: usize| refined_sample(&self.r1, i, scale);
+        let s2 = |j: usize| refined_sample(&self.r2, j, scale);
+        let point = |i: usize, j: usize| -> Vector3<f32> {
+            if i.is_multiple_of(scale) && j.is_multiple_of(scale) {
+                at((i / scale) % n1, (j / scale) % n2).into()
+            } else {
+                (self.p)(s1(i), s2(j), t).into()
+            }
+        };
+
+        let mut leaves = Vec::new();
+        for i in 0..cells1 {
+            for j in 0..cells2 {
+                self.refine(&point, (i * scale, j * scale), scale, &mut leaves);
+            }
+        }
+
+        let corners: HashSet<(usize, usize)> = leaves.iter()
+            .flat_map(|&(i, j, size)| [(i, j), (i + size, j), (i + size, j + size), (i, j + size)])
+            .map(|(i, j)| grid.canonical(i, j))
+            .collect();
+
+        // Texture coordinates run from 0 to 1 over the tessellated parameter ranges. Welded
+        // seams share their vertices, so they keep the coordinates of the first sample. A
+        // range without any span has nothing to run over, so it stays at 0.
+        let (end1, end2) = (s1(cells1 * scale), s2(cells2 * scale));
+        let fraction = |x: f32, start: f32, end: f32| if end != start { (x - start) / (end - start) } else { 0. };
+        let uv = |i: usize, j: usize| [fraction(s1(i), self.r1[0], end1), fraction(s2(j), self.r2[0], end2)];
+
+        let mut ids: HashMap<(usize, usize), u32> = HashMap::new();
         let mut vertices = Vec::new();
+        let mut parameters = Vec::new();
+        let mut singular = false;
+        let mut vertex_id = |i: usize, j: usize| -> u32 {
+            let (i, j) = grid.canonical(i, j);
+            *ids.entry((i, j)).or_insert_with(|| {
+                let normal = self.normal(s1(i), s2(j), t).unwrap_or_else(|| {
+                    singular = true;
+                    [0., 0., 0.]
+                });
+                vertices.push(Vertex::with_normal(point(i, j).into(), [0., 0., 0.], normal).with_uv(uv(i, j)));
+                parameters.push([s1(i), s2(j)]);
+                vertices.len() as u32 - 1
+            })
+        };
+
         let mut indices = Vec::new();
-        let l1 = self.r1.len() as u32;
-        let l2 = self.r2.len() as u32;
-
-        for &s1 in self.r1.iter() {
-            for &s2 in self.r2.iter() {
-                //println!("sphere({s1}, {s2}");
-                let new_vertex = Vertex::new((self.p)(s1, s2), [0.6, 0., 0.6]);
-                vertices.push(new_vertex);
+        for &(i, j, size) in leaves.iter() {
+            // The leaf's outline, counter-clockwise from its first corner, through every
+            // vertex that a finer neighbour put on its edges.
+            let mut outline = Vec::with_capacity(4);
+            let edges = [((i, j), (1, 0)), ((i + size, j), (0, 1)), ((i + size, j + size), (-1, 0)), ((i, j + size), (0, -1))];
+            for ((i0, j0), (di, dj)) in edges {
+                for k in 0..size {
+                    let (i, j) = ((i0 as isize + di * k as isize) as usize, (j0 as isize + dj * k as isize) as usize);
+                    if k == 0 || corners.contains(&grid.canonical(i, j)) {
+                        outline.push((i, j));
+                    }
+                }
+            }
+
+            if let [a, b, c, d] = outline[..] {
+                let [a, b, c, d] = [a, b, c, d].map(|(i, j)| vertex_id(i, j));
+                push_triangle(&mut indices, a, c, d);
+                push_triangle(&mut indices, a, b, c);
+            } else {
+                let center = vertex_id(i + size / 2, j + size / 2);
+                let outline: Vec<u32> = outline.into_iter().map(|(i, j)| vertex_id(i, j)).collect();
+                for (k, &a) in outline.iter().enumerate() {
+                    push_triangle(&mut indices, center, a, outline[(k + 1) % outline.len()]);
+                }
             }
         }
-        for i in 0..l1*l2-l2 -1 {
-            let i = i as u32;
-            indices.push(i);
-            indices.push(i+l2+1);
-            indices.push(i+1);
-            indices.push(i);
-            indices.push(i+l2);
-            indices.push(i+l2+1);
+
+        let positions: Vec<[f32; 3]> = vertices.iter().map(|v| v.position).collect();
+        for (vertex, color) in vertices.iter_mut().zip(self.coloring.colors(&parameters, &positions)) {
+            vertex.color = color;
+        }
+
+        // Where the derivatives vanish fall back to the normals of the surrounding triangles.
+        if singular {
+            let mut averaged = vertices.clone();
+            vertex::compute_normals(&mut averaged, &indices);
+            for (vertex, averaged) in vertices.iter_mut().zip(averaged) {
+                if vertex.normal == [0., 0., 0.] {
+                    vertex.normal = averaged.normal;
+                }
+            }
+        }
+
+        (vertices, indices)
     }
 
-        //print!("{:?}", vertices);
+    /// Splits the cell of the given `size` at `corner` on the refined grid into quarters
+    /// until it is flat enough, collecting the leaves as `(i, j, size)`.
+    fn refine<P>(&self, point: &P, corner: (usize, usize), size: usize, leaves: &mut Vec<(usize, usize, usize)>)
+    where
+        P: Fn(usize, usize) -> Vector3<f32>,
+    {
+        let (i, j) = corner;
+        let half = size / 2;
+        let split = half > 0 && self.adaptive.is_some_and(|adaptive| {
+            let [a, b, c, d] = [(i, j), (i + size, j), (i + size, j + size), (i, j + size)].map(|(i, j)| point(i, j));
+            let midpoints = [
+                ((i + half, j + half), (a + b + c + d) / 4.),
+                ((i + half, j), (a + b) / 2.),
+                ((i + size, j + half), (b + c) / 2.),
+                ((i + half, j + size), (c + d) / 2.),
+                ((i, j + half), (d + a) / 2.),
+            ];
+            midpoints.iter().any(|&((i, j), bilinear)| adaptive.exceeds(point(i, j), bilinear))
+        });
+
+        if split {
+            for (di, dj) in [(0, 0), (half, 0), (half, half), (0, half)] {
+                self.refine(point, (i + di, j + dj), half, leaves);
+            }
+        } else {
+            leaves.push((i, j, size));
+        }
+    }
+}
+
+/// The parameter value at index `i` of a grid `scale` times finer than `samples`.
+///
+/// Past the last sample, as in the closing cell of a periodic parameter, the spacing
+/// is taken to be the average spacing of the samples.
+fn refined_sample(samples: &[f32], i: usize, scale: usize) -> f32 {
+    let n = samples.len();
+    let (cell, k) = (i / scale, i % scale);
+    let sample = |i: usize| if i < n {
+        samples[i]
+    } else {
+        samples[n - 1] + (i - n + 1) as f32 * (samples[n - 1] - samples[0]) / (n - 1) as f32
+    };
+    let (a, b) = (sample(cell), sample(cell + 1));
+    a + (b - a) * k as f32 / scale as f32
+}
+
+/// Maps grid coordinates of a parameter grid onto the sample they are welded to.
+struct GridWeld {
+    n1: usize,
+    n2: usize,
+    cols: usize,
+    rows: usize,
+    b1: Boundary,
+    b2: Boundary,
+}
+
+impl GridWeld {
+    fn canonical(&self, i: usize, j: usize) -> (usize, usize) {
+        let i = if self.b1 == Boundary::Periodic { i % self.cols } else { i };
+        let j = if self.b2 == Boundary::Periodic { j % self.rows } else { j };
+        if self.b2 == Boundary::Degenerate && (j == 0 || j == self.n2 - 1) {
+            (0, j)
+        } else if self.b1 == Boundary::Degenerate && (i == 0 || i == self.n1 - 1) {
+            (i, 0)
+        } else {
+            (i, j)
+        }
+    }
+}
+
+fn coincident(a: [f32; 3], b: [f32; 3]) -> bool {
+    let scale = a.iter().chain(b.iter()).fold(1f32, |m, x| m.max(x.abs()));
+    a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() <= 1e-5 * scale)
+}
+
+/// Pushes a triangle unless welding collapsed two of its corners.
+fn push_triangle(indices: &mut Vec<u32>, a: u32, b: u32, c: u32) {
+    if a != b && b != c && c != a {
+        indices.extend_from_slice(&[a, b, c]);
+    }
+}
+
+impl Draw for ParametricSurface{
+    fn draw(&self, app: &App) -> Geometry {
+        let (vertices, indices) = self.tessellate(app.time());
         Geometry::new_mesh(app, &vertices, &indices)
     }
+
+    /// The surface at time 0. Use [`tessellate`](ParametricSurface::tessellate) for other times.
+    fn mesh(&self) -> Option<Mesh> {
+        let (vertices, indices) = self.tessellate(0.);
+        Some(Mesh::from_vertices(&vertices, &indices))
+    }
+
+    fn is_animated(&self) -> bool {
+        self.animated
+    }
 }
 
 pub struct PolyLine {
     vertices: Vec<Vertex>,
     indices: Vec<u32>,
+    coloring: Option<Coloring>,
+    style: Option<LineStyle>,
+    closed: bool,
 }
 
 impl PolyLine {
+    /// A line through `vertices` in order, drawn in their own colors.
     pub fn new(vertices: Vec<Vertex>) -> PolyLine {
-        let mut indices = Vec::new();
-        vertices.iter()
-            .enumerate()
-            .for_each(|(i, _)| {
-                indices.push(i as u32);
-                indices.push((i+1) as u32);
-            });
-        PolyLine {vertices, indices}
+        let indices = (1..vertices.len() as u32).flat_map(|i| [i - 1, i]).collect();
+        PolyLine { vertices, indices, coloring: None, style: None, closed: false }
+    }
+
+    /// A line through `points` in order, colored by [`PolyLine::coloring`].
+    pub fn from_points(points: &[[f32; 3]]) -> PolyLine {
+        PolyLine::new(points.iter().map(|&p| Vertex::new(p, mesh::DEFAULT_COLOR)).collect())
+            .coloring(Coloring::default())
+    }
+
+    /// Colors the line instead of using the colors of its vertices. The line's parameter
+    /// `u` runs from 0 at the first vertex to 1 at the last, evenly by vertex, and `v` is 0.
+    pub fn coloring(mut self, coloring: impl Into<Coloring>) -> PolyLine {
+        self.coloring = Some(coloring.into());
+        self
+    }
+
+    /// Draws the line `style.width` pixels wide with anti-aliased edges, rather than as
+    /// the thinnest line the GPU draws.
+    pub fn style(mut self, style: LineStyle) -> PolyLine {
+        self.style = Some(style);
+        self
+    }
+
+    /// Whether the line goes on from its last vertex back to its first.
+    pub fn closed(mut self, closed: bool) -> PolyLine {
+        self.closed = closed;
+        self
     }
 
     pub fn push(&mut self, vertex: Vertex) {
-        // let index = *self.indices.get(self.indices.len()-1).unwrap();
         let index = self.vertices.len() as u32;
         self.vertices.push(vertex);
-        self.indices.push(index-1);
-        self.indices.push(index);
+        if index > 0 {
+            self.indices.push(index - 1);
+            self.indices.push(index);
+        }
     }
 
     pub fn len(&self) -> usize {
         self.vertices.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// The vertices with the line's coloring applied, and the indices of its segments.
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let mut vertices = self.vertices.clone();
+        if let Some(coloring) = &self.coloring {
+            let last = vertices.len().saturating_sub(1).max(1) as f32;
+            let parameters: Vec<[f32; 2]> = (0..vertices.len()).map(|i| [i as f32 / last, 0.]).collect();
+            let positions: Vec<[f32; 3]> = vertices.iter().map(|v| v.position).collect();
+            for (vertex, color) in vertices.iter_mut().zip(coloring.colors(&parameters, &positions)) {
+                vertex.color = color;
+            }
+        }
+        let mut indices = self.indices.clone();
+        if self.closed && vertices.len() > 2 {
+            indices.extend([vertices.len() as u32 - 1, 0]);
+        }
+        (vertices, indices)
+    }
 }
 
 impl Draw for PolyLine {
     fn draw(&self, app: &App) -> Geometry {
-        
-        Geometry::new_line(app, &self.vertices, &self.indices)
+        let (vertices, indices) = self.tessellate();
+        match &self.style {
+            Some(style) => Geometry::new_thick_line(app, &vertices, self.closed, style),
+            None => Geometry::new_line(app, &vertices, &indices),
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,76 @@
+use cgmath::{InnerSpace, Matrix4, Vector3, Vector4};
+
+/// How far a surface may stray from its triangles before they are subdivided.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tolerance {
+    /// Distance in world units.
+    World(f32),
+    /// Distance in pixels after projecting with `view_proj` onto a `viewport` of the given size.
+    Screen {
+        view_proj: Matrix4<f32>,
+        viewport: [f32; 2],
+        pixels: f32,
+    },
+}
+
+/// The deepest a cell is ever split. Each level quarters the cells, so this is already
+/// far finer than a screen can show.
+pub const MAX_DEPTH: u32 = 16;
+
+/// Settings for tessellating a surface adaptively.
+///
+/// Each cell of the sampled parameter grid is split into quarters for as long as the
+/// surface at the middle of the cell, or at the middle of one of its edges, deviates
+/// from the bilinear patch through its corners by more than the tolerance, and at most
+/// `max_depth` times, which is never more than [`MAX_DEPTH`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Adaptive {
+    pub tolerance: Tolerance,
+    pub max_depth: u32,
+}
+
+impl Adaptive {
+    /// Refines until the surface is within `tolerance` world units of its triangles.
+    pub fn world(tolerance: f32) -> Adaptive {
+        Adaptive {
+            tolerance: Tolerance::World(tolerance),
+            max_depth: 4,
+        }
+    }
+
+    /// Refines until the surface is within `pixels` of its triangles on screen, for
+    /// a camera with the given view-projection matrix and a viewport of the given size.
+    pub fn screen(view_proj: Matrix4<f32>, viewport: [f32; 2], pixels: f32) -> Adaptive {
+        Adaptive {
+            tolerance: Tolerance::Screen { view_proj, viewport, pixels },
+            max_depth: 4,
+        }
+    }
+
+    /// Limits how many times a cell is split, up to [`MAX_DEPTH`].
+    pub fn max_depth(mut self, max_depth: u32) -> Adaptive {
+        self.max_depth = max_depth.min(MAX_DEPTH);
+        self
+    }
+
+    /// Whether `actual` is too far from `approximate` to be left unrefined.
+    pub(crate) fn exceeds(&self, actual: Vector3<f32>, approximate: Vector3<f32>) -> bool {
+        match self.tolerance {
+            Tolerance::World(tolerance) => (actual - approximate).magnitude() > tolerance,
+            Tolerance::Screen { view_proj, viewport, pixels } => {
+                let project = |p: Vector3<f32>| {
+                    let clip = view_proj * p.extend(1.);
+                    // Points behind the camera are never seen, so don't refine for them.
+                    (clip.w > 0.).then(|| {
+                        let Vector4 { x, y, w, .. } = clip;
+                        [x / w * viewport[0] / 2., y / w * viewport[1] / 2.]
+                    })
+                };
+                match (project(actual), project(approximate)) {
+                    (Some(a), Some(b)) => (a[0] - b[0]).hypot(a[1] - b[1]) > pixels,
+                    _ => false,
+                }
+            }
+        }
+    }
+}
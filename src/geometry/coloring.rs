@@ -0,0 +1,162 @@
+use super::mesh::DEFAULT_COLOR;
+use crate::colormap::{self, Colormap};
+
+/// A color as a function of the parameters of a curve or surface.
+pub type ParameterColorFn = Box<dyn Fn(f32, f32) -> [f32; 3] + Send + Sync>;
+
+/// A color as a function of position.
+pub type PositionColorFn = Box<dyn Fn([f32; 3]) -> [f32; 3] + Send + Sync>;
+
+/// A scalar as a function of the parameters and the position, mapped through a colormap.
+pub type ColorScalarFn = Box<dyn Fn(f32, f32, [f32; 3]) -> f32 + Send + Sync>;
+
+enum Scalars {
+    Function(ColorScalarFn),
+    /// One scalar per vertex, in the order the geometry makes them.
+    Values(Vec<f32>),
+}
+
+enum Rule {
+    Uniform([f32; 3]),
+    Parameters(ParameterColorFn),
+    Position(PositionColorFn),
+    Scalar { scalars: Scalars, colormap: Colormap, range: Option<[f32; 2]> },
+}
+
+/// How the vertices of a curve or surface are colored.
+///
+/// Surfaces pass their two parameters `(u, v)`. Curves pass their parameter as `u`, and
+/// as `v` the angle around a tube, the position across a ribbon from 0 to 1, or 0.
+///
+/// ```
+/// use draw3d::colormap::Colormap;
+/// use draw3d::geometry::Coloring;
+///
+/// let stripes = Coloring::by_parameters(|u, _| if (u * 10.).sin() > 0. { [1., 1., 1.] } else { [0., 0., 0.] });
+/// let height = Coloring::by_scalar(|_, _, [_, _, z]| z, Colormap::plasma());
+/// ```
+pub struct Coloring {
+    rule: Rule,
+}
+
+impl Coloring {
+    pub fn uniform(color: [f32; 3]) -> Coloring {
+        Coloring { rule: Rule::Uniform(color) }
+    }
+
+    pub fn by_parameters<F>(f: F) -> Coloring
+    where
+        F: Fn(f32, f32) -> [f32; 3] + Send + Sync + 'static,
+    {
+        Coloring { rule: Rule::Parameters(Box::new(f)) }
+    }
+
+    pub fn by_position<F>(f: F) -> Coloring
+    where
+        F: Fn([f32; 3]) -> [f32; 3] + Send + Sync + 'static,
+    {
+        Coloring { rule: Rule::Position(Box::new(f)) }
+    }
+
+    /// Maps a scalar of the parameters and position through `colormap`, from the smallest
+    /// scalar on the geometry to the largest unless a [`range`](Coloring::range) is given.
+    pub fn by_scalar<F>(f: F, colormap: Colormap) -> Coloring
+    where
+        F: Fn(f32, f32, [f32; 3]) -> f32 + Send + Sync + 'static,
+    {
+        Coloring { rule: Rule::Scalar { scalars: Scalars::Function(Box::new(f)), colormap, range: None } }
+    }
+
+    /// The scalars mapped to the ends of the colormap. Has no effect on other colorings.
+    pub fn range(mut self, min: f32, max: f32) -> Coloring {
+        if let Rule::Scalar { range, .. } = &mut self.rule {
+            *range = Some([min, max]);
+        }
+        self
+    }
+
+    /// Colors by one scalar per vertex, keeping the colormap and range of a coloring
+    /// that's already by scalar.
+    pub(crate) fn with_values(self, values: Vec<f32>) -> Coloring {
+        self.with_scalars(Scalars::Values(values))
+    }
+
+    /// Colors by `f`, keeping the colormap and range of a coloring that's already by scalar.
+    pub(crate) fn with_scalar_fn(self, f: ColorScalarFn) -> Coloring {
+        self.with_scalars(Scalars::Function(f))
+    }
+
+    fn with_scalars(self, scalars: Scalars) -> Coloring {
+        let (colormap, range) = match self.rule {
+            Rule::Scalar { colormap, range, .. } => (colormap, range),
+            _ => (Colormap::default(), None),
+        };
+        Coloring { rule: Rule::Scalar { scalars, colormap, range } }
+    }
+
+    /// Replaces the colormap of a coloring by scalar.
+    pub(crate) fn with_colormap(mut self, colormap: Colormap) -> Coloring {
+        if let Rule::Scalar { colormap: current, .. } = &mut self.rule {
+            *current = colormap;
+        }
+        self
+    }
+
+    pub(crate) fn colormap(&self) -> Option<&Colormap> {
+        match &self.rule {
+            Rule::Scalar { colormap, .. } => Some(colormap),
+            _ => None,
+        }
+    }
+
+    /// The scalars mapped to the ends of the colormap for vertices with the given
+    /// parameters and positions, or `None` if the coloring isn't by scalar.
+    pub(crate) fn scalar_range(&self, parameters: &[[f32; 2]], positions: &[[f32; 3]]) -> Option<[f32; 2]> {
+        match &self.rule {
+            Rule::Scalar { scalars, range, .. } => {
+                let scalars = scalars.eval(parameters, positions).unwrap_or_default();
+                Some(range.unwrap_or_else(|| colormap::finite_range(&scalars)))
+            }
+            _ => None,
+        }
+    }
+
+    /// The colors of vertices with the given parameters and positions.
+    pub(crate) fn colors(&self, parameters: &[[f32; 2]], positions: &[[f32; 3]]) -> Vec<[f32; 3]> {
+        match &self.rule {
+            Rule::Uniform(color) => vec![*color; positions.len()],
+            Rule::Parameters(f) => parameters.iter().map(|&[u, v]| f(u, v)).collect(),
+            Rule::Position(f) => positions.iter().map(|&p| f(p)).collect(),
+            Rule::Scalar { scalars, colormap, range } => {
+                let Some(scalars) = scalars.eval(parameters, positions) else {
+                    log::warn!("Coloring has the wrong number of values for the vertices, using the default color");
+                    return vec![DEFAULT_COLOR; positions.len()];
+                };
+                let [min, max] = range.unwrap_or_else(|| colormap::finite_range(&scalars));
+                scalars.iter().map(|&s| colormap.map_range(s, min, max)).collect()
+            }
+        }
+    }
+}
+
+impl Scalars {
+    /// The scalar of every vertex, or `None` if there aren't as many values as vertices.
+    fn eval(&self, parameters: &[[f32; 2]], positions: &[[f32; 3]]) -> Option<Vec<f32>> {
+        match self {
+            Scalars::Function(f) => Some(parameters.iter().zip(positions).map(|(&[u, v], &p)| f(u, v, p)).collect()),
+            Scalars::Values(values) => (values.len() == positions.len()).then(|| values.clone()),
+        }
+    }
+}
+
+impl Default for Coloring {
+    fn default() -> Coloring {
+        Coloring::uniform(DEFAULT_COLOR)
+    }
+}
+
+impl From<[f32; 3]> for Coloring {
+    fn from(color: [f32; 3]) -> Coloring {
+        Coloring::uniform(color)
+    }
+}
@@ -0,0 +1,335 @@
+use std::f32::consts::PI;
+
+use cgmath::{InnerSpace, Quaternion, Rad, Rotation, Rotation3, Vector3};
+
+use super::{Boundary, Coloring, Domain, Draw, Geometry, LineStyle, Mesh};
+use crate::app::App;
+use crate::vertex::Vertex;
+
+/// A point on a curve as a function of the curve parameter.
+pub type CurveFn = Box<dyn Fn(f32) -> [f32; 3] + Send + Sync>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Sweep {
+    Line { style: Option<LineStyle> },
+    Tube { radius: f32, sides: u32, caps: bool },
+    Ribbon { width: f32 },
+}
+
+/// An orthonormal frame moving along a curve.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    pub position: [f32; 3],
+    pub tangent: [f32; 3],
+    pub normal: [f32; 3],
+    pub binormal: [f32; 3],
+}
+
+/// A space curve `t -> [x, y, z]` sampled over a [`Domain`], drawn as a line or swept
+/// into a tube or a ribbon.
+///
+/// A periodic domain closes the curve into a loop.
+///
+/// ```
+/// use draw3d::geometry::{Domain, ParametricCurve};
+/// use std::f32::consts::PI;
+///
+/// let helix = ParametricCurve::new(|t| [t.cos(), t.sin(), t / 10.], Domain::linspace(0., 6. * PI, 300))
+///     .tube(0.05);
+/// ```
+pub struct ParametricCurve {
+    p: CurveFn,
+    domain: Domain,
+    coloring: Coloring,
+    sweep: Sweep,
+}
+
+impl ParametricCurve {
+    pub fn new<F>(p: F, domain: impl Into<Domain>) -> ParametricCurve
+    where
+        F: Fn(f32) -> [f32; 3] + Send + Sync + 'static,
+    {
+        ParametricCurve {
+            p: Box::new(p),
+            domain: domain.into(),
+            coloring: Coloring::default(),
+            sweep: Sweep::Line { style: None },
+        }
+    }
+
+    /// Colors the curve. The first parameter is the curve's, the second the angle
+    /// around a tube or the position across a ribbon from 0 to 1, and 0 on a line.
+    pub fn coloring(mut self, coloring: impl Into<Coloring>) -> ParametricCurve {
+        self.coloring = coloring.into();
+        self
+    }
+
+    /// Sweeps a circle of `radius` along the curve. The ends of open curves are capped.
+    pub fn tube(mut self, radius: f32) -> ParametricCurve {
+        self.sweep = Sweep::Tube { radius, sides: 16, caps: true };
+        self
+    }
+
+    /// The number of vertices around a tube.
+    pub fn sides(mut self, n: u32) -> ParametricCurve {
+        if let Sweep::Tube { sides, .. } = &mut self.sweep {
+            *sides = n.max(3);
+        }
+        self
+    }
+
+    /// Whether the ends of a tube along an open curve are closed off.
+    pub fn caps(mut self, closed: bool) -> ParametricCurve {
+        if let Sweep::Tube { caps, .. } = &mut self.sweep {
+            *caps = closed;
+        }
+        self
+    }
+
+    /// Sweeps a flat strip of `width` along the curve, lying across the curve's binormal.
+    /// The ribbon can be seen from both sides.
+    pub fn ribbon(mut self, width: f32) -> ParametricCurve {
+        self.sweep = Sweep::Ribbon { width };
+        self
+    }
+
+    /// Draws the curve as a line, undoing [`tube`](ParametricCurve::tube) or
+    /// [`ribbon`](ParametricCurve::ribbon).
+    pub fn line(mut self) -> ParametricCurve {
+        self.sweep = Sweep::Line { style: None };
+        self
+    }
+
+    /// Draws the curve as a line of constant width on screen, like a [`line`](ParametricCurve::line)
+    /// but with the width, caps, joins and dashes of `style`.
+    pub fn line_style(mut self, style: LineStyle) -> ParametricCurve {
+        self.sweep = Sweep::Line { style: Some(style) };
+        self
+    }
+
+    /// Whether the curve closes into a loop.
+    pub fn is_closed(&self) -> bool {
+        self.domain.boundary() == Boundary::Periodic
+    }
+
+    /// The sampled parameters and points. A closed curve whose last sample lands on its
+    /// first one leaves it out.
+    fn samples(&self) -> (Vec<f32>, Vec<Vector3<f32>>) {
+        let mut parameters = self.domain.values();
+        let mut points: Vec<Vector3<f32>> = parameters.iter().map(|&t| (self.p)(t).into()).collect();
+        if self.is_closed() && points.len() > 2 {
+            let (first, last) = (points[0], points[points.len() - 1]);
+            let scale = first.x.abs().max(first.y.abs()).max(first.z.abs()).max(1.);
+            if (first - last).magnitude() <= 1e-5 * scale {
+                points.pop();
+                parameters.pop();
+            }
+        }
+        (parameters, points)
+    }
+
+    /// Frames along the curve that turn as little as possible about the tangent from one
+    /// sample to the next, so sweeps along them don't twist.
+    ///
+    /// Each frame is the previous one rotated by the smallest rotation taking the previous
+    /// tangent to the next one. On a closed curve, whatever twist is left over when the
+    /// frames come back round is spread evenly along it, so the ends meet up.
+    pub fn frames(&self) -> Vec<Frame> {
+        let (_, points) = self.samples();
+        parallel_transport(&points, self.is_closed())
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let (parameters, points) = self.samples();
+        let n = points.len();
+        if n < 2 {
+            return (Vec::new(), Vec::new());
+        }
+        let closed = self.is_closed();
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        // The coloring parameters of each vertex.
+        let mut uvs = Vec::new();
+
+        match self.sweep {
+            Sweep::Line { .. } => {
+                for (&point, &t) in points.iter().zip(parameters.iter()) {
+                    vertices.push(Vertex::new(point.into(), [0., 0., 0.]));
+                    uvs.push([t, 0.]);
+                }
+                let segments = if closed { n } else { n - 1 } as u32;
+                indices.extend((0..segments).flat_map(|i| [i, (i + 1) % n as u32]));
+            }
+            Sweep::Tube { radius, sides, caps } => {
+                let frames = parallel_transport(&points, closed);
+                let columns = sides + 1;
+                for (i, (frame, &t)) in frames.iter().zip(parameters.iter()).enumerate() {
+                    let [normal, binormal] = [frame.normal, frame.binormal].map(Vector3::from);
+                    for k in 0..columns {
+                        let angle = 2. * PI * k as f32 / sides as f32;
+                        let direction = normal * angle.cos() + binormal * angle.sin();
+                        let position = Vector3::from(frame.position) + direction * radius;
+                        let uv = [i as f32 / (n - 1) as f32, k as f32 / sides as f32];
+                        vertices.push(Vertex::with_normal(position.into(), [0., 0., 0.], direction.into()).with_uv(uv));
+                        uvs.push([t, angle]);
+                    }
+                }
+
+                let rings = if closed { n } else { n - 1 } as u32;
+                for i in 0..rings {
+                    let next = (i + 1) % n as u32;
+                    for k in 0..sides {
+                        let [a, b] = [i * columns + k, i * columns + k + 1];
+                        let [c, d] = [next * columns + k + 1, next * columns + k];
+                        indices.extend_from_slice(&[a, b, c, a, c, d]);
+                    }
+                }
+
+                if caps && !closed {
+                    for (end, &t) in [(0, &parameters[0]), (n - 1, &parameters[n - 1])] {
+                        let frame = frames[end];
+                        let facing = if end == 0 { -1. } else { 1. };
+                        let normal = (Vector3::from(frame.tangent) * facing).into();
+                        let center = vertices.len() as u32;
+                        vertices.push(Vertex::with_normal(frame.position, [0., 0., 0.], normal).with_uv([0.5, 0.5]));
+                        uvs.push([t, 0.]);
+                        for k in 0..sides {
+                            let ring = vertices[end * columns as usize + k as usize];
+                            let angle = 2. * PI * k as f32 / sides as f32;
+                            let uv = [0.5 + angle.cos() / 2., 0.5 + angle.sin() / 2.];
+                            vertices.push(Vertex::with_normal(ring.position, [0., 0., 0.], normal).with_uv(uv));
+                            uvs.push([t, angle]);
+                        }
+                        // The ring turns counter-clockwise about the tangent, so the start
+                        // cap, which faces back along it, winds the other way.
+                        for k in 0..sides {
+                            let (a, b) = (center + 1 + k, center + 1 + (k + 1) % sides);
+                            if end == 0 {
+                                indices.extend_from_slice(&[center, b, a]);
+                            } else {
+                                indices.extend_from_slice(&[center, a, b]);
+                            }
+                        }
+                    }
+                }
+            }
+            Sweep::Ribbon { width } => {
+                let frames = parallel_transport(&points, closed);
+                for (i, (frame, &t)) in frames.iter().zip(parameters.iter()).enumerate() {
+                    let binormal = Vector3::from(frame.binormal) * (width / 2.);
+                    let position = Vector3::from(frame.position);
+                    let along = i as f32 / (n - 1) as f32;
+                    for (side, across) in [(-1., 0.), (1., 1.)] {
+                        let vertex = Vertex::with_normal((position + binormal * side).into(), [0., 0., 0.], frame.normal);
+                        vertices.push(vertex.with_uv([along, across]));
+                        uvs.push([t, across]);
+                    }
+                }
+                // Back faces are culled, so the underside gets its own flipped copy.
+                let front = vertices.len() as u32;
+                for k in 0..front as usize {
+                    let mut vertex = vertices[k];
+                    vertex.normal = vertex.normal.map(|x| -x);
+                    vertices.push(vertex);
+                    uvs.push(uvs[k]);
+                }
+
+                let strips = if closed { n } else { n - 1 } as u32;
+                for i in 0..strips {
+                    let next = (i + 1) % n as u32;
+                    let [a, b, c, d] = [2 * i, 2 * i + 1, 2 * next + 1, 2 * next];
+                    indices.extend_from_slice(&[a, b, c, a, c, d]);
+                    indices.extend_from_slice(&[front + a, front + c, front + b, front + a, front + d, front + c]);
+                }
+            }
+        }
+
+        let positions: Vec<[f32; 3]> = vertices.iter().map(|v| v.position).collect();
+        for (vertex, color) in vertices.iter_mut().zip(self.coloring.colors(&uvs, &positions)) {
+            vertex.color = color;
+        }
+        (vertices, indices)
+    }
+}
+
+fn parallel_transport(points: &[Vector3<f32>], closed: bool) -> Vec<Frame> {
+    let n = points.len();
+    if n < 2 {
+        return Vec::new();
+    }
+
+    let tangents: Vec<Vector3<f32>> = (0..n).map(|i| {
+        let (before, after) = if closed {
+            (points[(i + n - 1) % n], points[(i + 1) % n])
+        } else {
+            (points[i.saturating_sub(1)], points[(i + 1).min(n - 1)])
+        };
+        let tangent = after - before;
+        if tangent.magnitude2() > 0. { tangent.normalize() } else { Vector3::unit_z() }
+    }).collect();
+
+    // Start from whichever axis is furthest from the first tangent.
+    let t0 = tangents[0];
+    let axis = [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()]
+        .into_iter()
+        .min_by(|a, b| a.dot(t0).abs().total_cmp(&b.dot(t0).abs()))
+        .unwrap();
+    let mut normal = (axis - t0 * axis.dot(t0)).normalize();
+
+    let mut normals = Vec::with_capacity(n);
+    normals.push(normal);
+    for i in 1..n {
+        normal = transport(normal, tangents[i - 1], tangents[i]);
+        normals.push(normal);
+    }
+
+    if closed {
+        // Carry the last frame round to the first tangent and see how far it has turned.
+        let arrived = transport(normal, tangents[n - 1], t0);
+        let twist = normals[0].cross(arrived).dot(t0).atan2(normals[0].dot(arrived));
+        for (i, normal) in normals.iter_mut().enumerate() {
+            let correction = Quaternion::from_axis_angle(tangents[i], Rad(-twist * i as f32 / n as f32));
+            *normal = correction.rotate_vector(*normal);
+        }
+    }
+
+    (0..n).map(|i| {
+        let (tangent, normal) = (tangents[i], normals[i]);
+        Frame {
+            position: points[i].into(),
+            tangent: tangent.into(),
+            normal: normal.into(),
+            binormal: tangent.cross(normal).into(),
+        }
+    }).collect()
+}
+
+/// Rotates `v` by the smallest rotation taking the unit vector `from` to `to`.
+fn transport(v: Vector3<f32>, from: Vector3<f32>, to: Vector3<f32>) -> Vector3<f32> {
+    let v = Quaternion::from_arc(from, to, None).rotate_vector(v);
+    // Keep the frame orthonormal despite rounding.
+    (v - to * v.dot(to)).normalize()
+}
+
+impl Draw for ParametricCurve {
+    fn draw(&self, app: &App) -> Geometry {
+        let (vertices, indices) = self.tessellate();
+        match self.sweep {
+            Sweep::Line { style: None } => Geometry::new_line(app, &vertices, &indices),
+            Sweep::Line { style: Some(style) } => Geometry::new_thick_line(app, &vertices, self.is_closed(), &style),
+            Sweep::Tube { .. } | Sweep::Ribbon { .. } => Geometry::new_mesh(app, &vertices, &indices),
+        }
+    }
+
+    /// The tube or ribbon around the curve, or `None` for a line.
+    fn mesh(&self) -> Option<Mesh> {
+        match self.sweep {
+            Sweep::Line { .. } => None,
+            Sweep::Tube { .. } | Sweep::Ribbon { .. } => {
+                let (vertices, indices) = self.tessellate();
+                Some(Mesh::from_vertices(&vertices, &indices))
+            }
+        }
+    }
+}
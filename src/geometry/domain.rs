@@ -0,0 +1,163 @@
+/// How the tessellation treats the ends of a surface parameter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Boundary {
+    /// The surface has an edge at the first and last sample.
+    #[default]
+    Open,
+    /// The parameter wraps around, like longitude on a sphere, so the last sample
+    /// is joined back to the first. A last sample that lands on the first one
+    /// (e.g. both 0 and 2π are sampled) is welded onto it.
+    Periodic,
+    /// The surface collapses to a single point at the first and at the last sample,
+    /// like latitude at the poles of a sphere. Those ends are closed with triangle fans.
+    Degenerate,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Sampling {
+    Linear { start: f32, end: f32, n: usize },
+    Arange { start: f32, stop: f32, step: f32 },
+    Log { start: f32, end: f32, n: usize },
+    Chebyshev { start: f32, end: f32, n: usize },
+    Explicit(Vec<f32>),
+}
+
+/// The values a curve or surface parameter is sampled at, and how its ends join up.
+///
+/// ```
+/// use draw3d::geometry::Domain;
+/// use std::f32::consts::PI;
+///
+/// // 32 longitudes that don't repeat 0 at 2π.
+/// let theta = Domain::linspace(0., 2. * PI, 32).periodic();
+/// assert_eq!(theta.values().len(), 32);
+/// assert!(theta.values()[31] < 2. * PI);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Domain {
+    sampling: Sampling,
+    endpoint: bool,
+    boundary: Boundary,
+}
+
+impl Domain {
+    fn from_sampling(sampling: Sampling) -> Domain {
+        Domain { sampling, endpoint: true, boundary: Boundary::Open }
+    }
+
+    /// `n` evenly spaced samples from `start` to `end`.
+    pub fn linspace(start: f32, end: f32, n: usize) -> Domain {
+        Domain::from_sampling(Sampling::Linear { start, end, n })
+    }
+
+    /// Samples from `start` in increments of `step`, stopping before `stop`.
+    pub fn arange(start: f32, stop: f32, step: f32) -> Domain {
+        Domain::from_sampling(Sampling::Arange { start, stop, step }).endpoint(false)
+    }
+
+    /// `n` samples from `start` to `end` spaced evenly on a logarithmic scale.
+    ///
+    /// # Panics
+    ///
+    /// If either end is zero, or the ends have different signs.
+    pub fn logspace(start: f32, end: f32, n: usize) -> Domain {
+        assert!(
+            start != 0. && end != 0. && start.signum() == end.signum(),
+            "logspace needs non-zero ends of the same sign, got {} and {}", start, end,
+        );
+        Domain::from_sampling(Sampling::Log { start, end, n })
+    }
+
+    /// `n` Chebyshev nodes on `[start, end]`, which cluster towards the ends of the interval.
+    ///
+    /// With the endpoint included (the default) these are the Chebyshev–Lobatto points,
+    /// which contain both ends; without it they are the Chebyshev–Gauss points, which
+    /// contain neither.
+    pub fn chebyshev(start: f32, end: f32, n: usize) -> Domain {
+        Domain::from_sampling(Sampling::Chebyshev { start, end, n })
+    }
+
+    /// Exactly the given samples.
+    pub fn samples(values: Vec<f32>) -> Domain {
+        Domain::from_sampling(Sampling::Explicit(values))
+    }
+
+    /// Whether `end` (or `stop` for `arange`) is sampled. Has no effect on explicit samples.
+    pub fn endpoint(mut self, include: bool) -> Domain {
+        self.endpoint = include;
+        self
+    }
+
+    /// Marks the parameter as wrapping around and leaves out the endpoint, which
+    /// would coincide with the first sample.
+    pub fn periodic(self) -> Domain {
+        self.endpoint(false).with_boundary(Boundary::Periodic)
+    }
+
+    /// Marks the surface as collapsing to a point at both ends of the parameter.
+    pub fn degenerate(self) -> Domain {
+        self.with_boundary(Boundary::Degenerate)
+    }
+
+    pub fn with_boundary(mut self, boundary: Boundary) -> Domain {
+        self.boundary = boundary;
+        self
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    pub fn values(&self) -> Vec<f32> {
+        match self.sampling {
+            Sampling::Linear { start, end, n } => evenly_spaced(start, end, n, self.endpoint),
+            Sampling::Log { start, end, n } => evenly_spaced(start.abs().ln(), end.abs().ln(), n, self.endpoint)
+                .into_iter()
+                .map(|x| x.exp().copysign(start))
+                .collect(),
+            Sampling::Arange { start, stop, step } => {
+                if step == 0. || (stop - start) * step < 0. {
+                    return Vec::new();
+                }
+                let steps = (stop - start) / step;
+                // Allow for rounding when deciding whether `stop` lands on a step.
+                let mut n = (steps - 1e-4).ceil().max(0.) as usize;
+                if self.endpoint && (steps - steps.round()).abs() < 1e-4 {
+                    n = steps.round() as usize + 1;
+                }
+                (0..n).map(|i| start + i as f32 * step).collect()
+            }
+            Sampling::Chebyshev { start, end, n } => {
+                let (mid, half) = ((start + end) / 2., (end - start) / 2.);
+                let angle = |k: usize| if self.endpoint {
+                    std::f32::consts::PI * k as f32 / (n.max(2) - 1) as f32
+                } else {
+                    std::f32::consts::PI * (2 * k + 1) as f32 / (2 * n) as f32
+                };
+                // cos decreases over [0, π], so walk k backwards for ascending samples.
+                (0..n).rev().map(|k| mid + half * angle(k).cos()).collect()
+            }
+            Sampling::Explicit(ref values) => values.clone(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl From<Vec<f32>> for Domain {
+    fn from(values: Vec<f32>) -> Domain {
+        Domain::samples(values)
+    }
+}
+
+fn evenly_spaced(start: f32, end: f32, n: usize, endpoint: bool) -> Vec<f32> {
+    let intervals = if endpoint { n.saturating_sub(1).max(1) } else { n.max(1) };
+    let step = (end - start) / intervals as f32;
+    (0..n).map(|i| start + i as f32 * step).collect()
+}
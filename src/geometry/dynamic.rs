@@ -0,0 +1,253 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::{Draw, Geometry, GeometryType};
+use crate::app::App;
+use crate::vertex::Vertex;
+
+/// A line kept on the GPU that grows as points are pushed onto it, for plotting data as
+/// it comes in.
+///
+/// Each push writes only the new vertices. When the buffer is full its capacity doubles,
+/// copying what's there on the GPU. A [`ring`](DynamicGeometry::ring) never grows and
+/// keeps only the latest points instead.
+///
+/// ```no_run
+/// use draw3d::app::App;
+/// use draw3d::geometry::DynamicGeometry;
+/// use draw3d::vertex::Vertex;
+///
+/// struct Model {
+///     trail: DynamicGeometry,
+///     t: f32,
+/// }
+///
+/// fn model(app: &App) -> Model {
+///     Model { trail: DynamicGeometry::ring(app, 500), t: 0. }
+/// }
+///
+/// fn update(app: &App, model: &mut Model) {
+///     model.t += 0.05;
+///     let t = model.t;
+///     model.trail.push(app, Vertex::new([t.cos(), t.sin(), t / 20.], [1., 1., 1.]));
+/// }
+///
+/// fn view(app: &mut App, model: &Model) {
+///     let mut draw = app.draw();
+///     draw.add(&model.trail, app);
+///     app.draw_to_frame(draw);
+/// }
+/// ```
+pub struct DynamicGeometry {
+    vertex_buffer: Arc<wgpu::Buffer>,
+    index_buffer: Arc<wgpu::Buffer>,
+    slots: DynamicSlots,
+}
+
+impl DynamicGeometry {
+    /// An empty line with room for a few vertices before it first grows.
+    pub fn line(app: &App) -> DynamicGeometry {
+        DynamicGeometry::with_capacity(app, 64)
+    }
+
+    /// An empty line with room for `capacity` vertices before it first grows.
+    pub fn with_capacity(app: &App, capacity: usize) -> DynamicGeometry {
+        DynamicGeometry::allocate(app, DynamicSlots::line(capacity))
+    }
+
+    /// An empty line that keeps only the last `capacity` vertices pushed onto it.
+    pub fn ring(app: &App, capacity: usize) -> DynamicGeometry {
+        DynamicGeometry::allocate(app, DynamicSlots::ring(capacity))
+    }
+
+    fn allocate(app: &App, slots: DynamicSlots) -> DynamicGeometry {
+        DynamicGeometry {
+            vertex_buffer: Arc::new(vertex_buffer(app, slots.capacity())),
+            index_buffer: Arc::new(index_buffer(app, slots.capacity(), slots.is_ring())),
+            slots,
+        }
+    }
+
+    pub fn push(&mut self, app: &App, vertex: Vertex) {
+        self.extend(app, &[vertex]);
+    }
+
+    /// Appends `vertices` to the line, writing just them to the GPU.
+    pub fn extend(&mut self, app: &App, vertices: &[Vertex]) {
+        let capacity = self.slots.capacity();
+        let writes = self.slots.extend(vertices.len());
+        if writes.grow_to > capacity {
+            self.grow(app, writes.grow_to, writes.kept);
+        }
+        for (slot, range) in writes.writes {
+            let offset = (slot * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress;
+            app.queue().write_buffer(&self.vertex_buffer, offset, bytemuck::cast_slice(&vertices[range]));
+        }
+    }
+
+    /// Removes every vertex, keeping the buffers for the next ones.
+    pub fn clear(&mut self) {
+        self.slots.clear();
+    }
+
+    /// The number of vertices drawn.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// The number of vertices the line holds before it grows, or the number a ring keeps.
+    pub fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+
+    pub fn is_ring(&self) -> bool {
+        self.slots.is_ring()
+    }
+
+    /// The indices of the segments from the oldest vertex kept to the newest.
+    pub fn index_range(&self) -> std::ops::Range<u32> {
+        self.slots.index_range()
+    }
+
+    /// Moves the line into buffers with room for `capacity` vertices, copying the first
+    /// `kept` vertices over.
+    fn grow(&mut self, app: &App, capacity: usize, kept: usize) {
+        let buffer = vertex_buffer(app, capacity);
+        let size = (kept * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress;
+        if size > 0 {
+            let mut encoder = app.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Dynamic Geometry Grow Encoder"),
+            });
+            encoder.copy_buffer_to_buffer(&self.vertex_buffer, 0, &buffer, 0, size);
+            // Earlier writes to the old buffer land before this copy runs.
+            app.queue().submit(std::iter::once(encoder.finish()));
+        }
+
+        self.vertex_buffer = Arc::new(buffer);
+        self.index_buffer = Arc::new(index_buffer(app, capacity, false));
+    }
+}
+
+/// Where the vertices of a [`DynamicGeometry`] go in its buffers, kept apart from the
+/// buffers themselves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DynamicSlots {
+    capacity: usize,
+    /// The number of vertices pushed since the line was created or cleared.
+    pushed: usize,
+    ring: bool,
+}
+
+/// What a [`DynamicSlots::extend`] needs done to the buffers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlotWrites {
+    /// The capacity the buffers must have, larger than before if they have to grow.
+    pub grow_to: usize,
+    /// The number of vertices to copy into the grown buffers.
+    pub kept: usize,
+    /// The slot to write each range of the new vertices to.
+    pub writes: Vec<(usize, Range<usize>)>,
+}
+
+impl DynamicSlots {
+    pub fn line(capacity: usize) -> DynamicSlots {
+        DynamicSlots { capacity: capacity.max(2), pushed: 0, ring: false }
+    }
+
+    pub fn ring(capacity: usize) -> DynamicSlots {
+        DynamicSlots { capacity: capacity.max(2), pushed: 0, ring: true }
+    }
+
+    /// Makes room for `count` more vertices, returning where to write them.
+    pub fn extend(&mut self, count: usize) -> SlotWrites {
+        let kept = self.pushed;
+        let mut writes = Vec::new();
+        if count > 0 && self.ring {
+            // Older vertices would be overwritten within this write anyway.
+            let skipped = count.saturating_sub(self.capacity);
+            let mut slot = (self.pushed + skipped) % self.capacity;
+            let mut start = skipped;
+            while start < count {
+                let end = count.min(start + self.capacity - slot);
+                writes.push((slot, start..end));
+                slot = 0;
+                start = end;
+            }
+        } else if count > 0 {
+            let len = self.pushed + count;
+            if len > self.capacity {
+                self.capacity = len.next_power_of_two();
+            }
+            writes.push((self.pushed, 0..count));
+        }
+        self.pushed += count;
+        SlotWrites { grow_to: self.capacity, kept, writes }
+    }
+
+    pub fn clear(&mut self) {
+        self.pushed = 0;
+    }
+
+    /// The number of vertices drawn.
+    pub fn len(&self) -> usize {
+        if self.ring { self.pushed.min(self.capacity) } else { self.pushed }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pushed == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn is_ring(&self) -> bool {
+        self.ring
+    }
+
+    /// The indices of the segments from the oldest vertex kept to the newest, in an index
+    /// buffer whose segments go round a ring twice.
+    pub fn index_range(&self) -> Range<u32> {
+        let segments = self.len().saturating_sub(1) as u32;
+        // A full ring starts at the oldest vertex, which the next push overwrites.
+        let first = if self.ring && self.pushed >= self.capacity { (self.pushed % self.capacity) as u32 } else { 0 };
+        2 * first..2 * (first + segments)
+    }
+}
+
+fn vertex_buffer(app: &App, capacity: usize) -> wgpu::Buffer {
+    app.device().create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Dynamic Vertex Buffer"),
+        size: (capacity * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    })
+}
+
+/// Segments between consecutive slots. A ring's segments go round twice, so the segments
+/// from its oldest vertex to its newest are contiguous wherever the oldest one is.
+fn index_buffer(app: &App, capacity: usize, ring: bool) -> wgpu::Buffer {
+    use wgpu::util::DeviceExt;
+
+    let capacity = capacity as u32;
+    let indices: Vec<u32> = if ring {
+        (0..2 * capacity).flat_map(|i| [i % capacity, (i + 1) % capacity]).collect()
+    } else {
+        (1..capacity).flat_map(|i| [i - 1, i]).collect()
+    };
+    app.device().create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Dynamic Index Buffer"),
+        contents: bytemuck::cast_slice(&indices),
+        usage: wgpu::BufferUsages::INDEX,
+    })
+}
+
+impl Draw for DynamicGeometry {
+    fn draw(&self, _app: &App) -> Geometry {
+        Geometry::from_buffers(GeometryType::Line, self.vertex_buffer.clone(), self.index_buffer.clone(), self.index_range())
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+use cgmath::{InnerSpace, Vector3};
+
+use super::mesh::DEFAULT_COLOR;
+use super::{Draw, Geometry, Mesh};
+use crate::app::App;
+use crate::vertex::Vertex;
+
+/// A scalar field as a function of position.
+pub type Field = Box<dyn Fn([f32; 3]) -> f32 + Send + Sync>;
+
+enum Source {
+    Function { f: Field, resolution: [usize; 3] },
+    /// Samples with x varying fastest, then y, then z.
+    Grid { values: Vec<f32>, dims: [usize; 3] },
+}
+
+/// The level set `f(p) = iso` of a scalar field inside a box, meshed with marching cubes.
+///
+/// Points where the field is below the iso level are inside the surface, so the
+/// triangles and the normals face the direction the field increases in.
+///
+/// ```
+/// use draw3d::geometry::ImplicitSurface;
+///
+/// let hyperboloid = ImplicitSurface::new(|[x, y, z]| x * x + y * y - z * z - 1., [-2.; 3], [2.; 3])
+///     .resolution(48);
+/// ```
+pub struct ImplicitSurface {
+    source: Source,
+    min: [f32; 3],
+    max: [f32; 3],
+    iso: f32,
+    color: [f32; 3],
+}
+
+impl ImplicitSurface {
+    /// Samples `f` on a grid of 32 cells along each axis of the box from `min` to `max`.
+    pub fn new<F>(f: F, min: [f32; 3], max: [f32; 3]) -> ImplicitSurface
+    where
+        F: Fn([f32; 3]) -> f32 + Send + Sync + 'static,
+    {
+        ImplicitSurface {
+            source: Source::Function { f: Box::new(f), resolution: [32; 3] },
+            min,
+            max,
+            iso: 0.,
+            color: DEFAULT_COLOR,
+        }
+    }
+
+    /// A surface through already sampled values, e.g. from a simulation, spread evenly over
+    /// the box from `min` to `max`. `values` holds `dims[0] * dims[1] * dims[2]` samples with
+    /// x varying fastest, then y, then z.
+    pub fn from_grid(values: Vec<f32>, dims: [usize; 3], min: [f32; 3], max: [f32; 3]) -> ImplicitSurface {
+        assert_eq!(values.len(), dims.iter().product::<usize>(), "grid has the wrong number of values");
+        ImplicitSurface {
+            source: Source::Grid { values, dims },
+            min,
+            max,
+            iso: 0.,
+            color: DEFAULT_COLOR,
+        }
+    }
+
+    /// The number of cells along every axis. Has no effect on sampled grids.
+    pub fn resolution(self, cells: usize) -> ImplicitSurface {
+        self.resolution_xyz([cells; 3])
+    }
+
+    /// The number of cells along x, y and z. Has no effect on sampled grids.
+    pub fn resolution_xyz(mut self, cells: [usize; 3]) -> ImplicitSurface {
+        if let Source::Function { resolution, .. } = &mut self.source {
+            *resolution = cells.map(|n| n.max(1));
+        }
+        self
+    }
+
+    /// The value of the field on the surface, 0 by default.
+    pub fn iso_level(mut self, iso: f32) -> ImplicitSurface {
+        self.iso = iso;
+        self
+    }
+
+    pub fn color(mut self, color: [f32; 3]) -> ImplicitSurface {
+        self.color = color;
+        self
+    }
+
+    fn dims(&self) -> [usize; 3] {
+        match &self.source {
+            Source::Function { resolution, .. } => resolution.map(|n| n + 1),
+            Source::Grid { dims, .. } => *dims,
+        }
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let [nx, ny, nz] = self.dims();
+        if nx < 2 || ny < 2 || nz < 2 {
+            return (Vec::new(), Vec::new());
+        }
+        let step = [0, 1, 2].map(|a| (self.max[a] - self.min[a]) / (self.dims()[a] - 1) as f32);
+        let position = |[x, y, z]: [usize; 3]| -> Vector3<f32> {
+            Vector3::new(
+                self.min[0] + x as f32 * step[0],
+                self.min[1] + y as f32 * step[1],
+                self.min[2] + z as f32 * step[2],
+            )
+        };
+        let index = |[x, y, z]: [usize; 3]| x + nx * (y + ny * z);
+
+        let sampled;
+        let values = match &self.source {
+            Source::Function { f, .. } => {
+                sampled = (0..nz)
+                    .flat_map(|z| (0..ny).flat_map(move |y| (0..nx).map(move |x| [x, y, z])))
+                    .map(|p| f(position(p).into()))
+                    .collect::<Vec<_>>();
+                &sampled
+            }
+            Source::Grid { values, .. } => values,
+        };
+
+        // Gradients of sampled grids come from the neighbouring samples, one sided at the
+        // edges of the grid, and are interpolated along the edges like the positions are.
+        let grid_gradient = |p: [usize; 3]| -> Vector3<f32> {
+            let mut gradient = [0.; 3];
+            for (axis, g) in gradient.iter_mut().enumerate() {
+                let (mut lo, mut hi) = (p, p);
+                lo[axis] = p[axis].saturating_sub(1);
+                hi[axis] = (p[axis] + 1).min(self.dims()[axis] - 1);
+                *g = (values[index(hi)] - values[index(lo)]) / ((hi[axis] - lo[axis]) as f32 * step[axis]);
+            }
+            gradient.into()
+        };
+        let normal = |p: Vector3<f32>, a: [usize; 3], b: [usize; 3], t: f32| -> [f32; 3] {
+            let gradient = match &self.source {
+                Source::Function { f, .. } => {
+                    let h = step.map(|s| s * 1e-2);
+                    let d = |axis: usize| {
+                        let mut offset = Vector3::new(0., 0., 0.);
+                        offset[axis] = h[axis];
+                        (f((p + offset).into()) - f((p - offset).into())) / (2. * h[axis])
+                    };
+                    Vector3::new(d(0), d(1), d(2))
+                }
+                Source::Grid { .. } => grid_gradient(a) * (1. - t) + grid_gradient(b) * t,
+            };
+            if gradient.magnitude2() > 0. {
+                gradient.normalize().into()
+            } else {
+                [0., 0., 0.]
+            }
+        };
+
+        let table = triangle_table();
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        // Vertices are shared by the cells around an edge, keyed by the edge's lower
+        // grid point and its axis.
+        let mut ids: HashMap<(usize, usize), u32> = HashMap::new();
+
+        for z in 0..nz - 1 {
+            for y in 0..ny - 1 {
+                for x in 0..nx - 1 {
+                    let corner = |c: usize| [x + (c & 1), y + ((c >> 1) & 1), z + ((c >> 2) & 1)];
+                    let case = (0..8).fold(0, |case, c| {
+                        if values[index(corner(c))] < self.iso { case | 1 << c } else { case }
+                    });
+
+                    let case = &table[case];
+                    let mut edge_vertex = |edge: u8, vertices: &mut Vec<Vertex>| -> u32 {
+                        let (c0, c1) = EDGES[edge as usize];
+                        let (p0, p1) = (corner(c0), corner(c1));
+                        let axis = (c0 ^ c1).trailing_zeros() as usize;
+                        *ids.entry((index(p0), axis)).or_insert_with(|| {
+                            let (v0, v1) = (values[index(p0)], values[index(p1)]);
+                            let t = ((self.iso - v0) / (v1 - v0)).clamp(0., 1.);
+                            let p = position(p0) + (position(p1) - position(p0)) * t;
+                            vertices.push(Vertex::with_normal(p.into(), self.color, normal(p, p0, p1, t)));
+                            vertices.len() as u32 - 1
+                        })
+                    };
+
+                    // Loops fanned from their middle get a vertex there, shared with no other cube.
+                    let centers: Vec<u32> = case.centers.iter().map(|polygon| {
+                        let (mut p, mut n) = (Vector3::new(0., 0., 0.), Vector3::new(0., 0., 0.));
+                        for &edge in polygon {
+                            let id = edge_vertex(edge, &mut vertices);
+                            let v = vertices[id as usize];
+                            p += Vector3::from(v.position);
+                            n += Vector3::from(v.normal);
+                        }
+                        let n = if n.magnitude2() > 0. { n.normalize().into() } else { [0., 0., 0.] };
+                        vertices.push(Vertex::with_normal((p / polygon.len() as f32).into(), self.color, n));
+                        vertices.len() as u32 - 1
+                    }).collect();
+
+                    for triangle in case.triangles.iter() {
+                        let [a, b, c] = triangle.map(|entry| match entry.checked_sub(CENTER) {
+                            Some(k) => centers[k as usize],
+                            None => edge_vertex(entry, &mut vertices),
+                        });
+                        indices.extend_from_slice(&[a, b, c]);
+                    }
+                }
+            }
+        }
+
+        (vertices, indices)
+    }
+}
+
+impl Draw for ImplicitSurface {
+    fn draw(&self, app: &App) -> Geometry {
+        let (vertices, indices) = self.tessellate();
+        Geometry::new_mesh(app, &vertices, &indices)
+    }
+
+    fn mesh(&self) -> Option<Mesh> {
+        let (vertices, indices) = self.tessellate();
+        Some(Mesh::from_vertices(&vertices, &indices))
+    }
+}
+
+// Cube corner `c` sits at `(c & 1, c >> 1 & 1, c >> 2 & 1)`.
+
+/// The corners joined by each cube edge, lower corner first.
+const EDGES: [(usize, usize); 12] = [
+    (0, 1), (2, 3), (4, 5), (6, 7),
+    (0, 2), (1, 3), (4, 6), (5, 7),
+    (0, 4), (1, 5), (2, 6), (3, 7),
+];
+
+/// The corners of each cube face, counter-clockwise seen from outside the cube.
+const FACES: [[usize; 4]; 6] = [
+    [0, 2, 3, 1], [4, 5, 7, 6],
+    [0, 1, 5, 4], [2, 6, 7, 3],
+    [0, 4, 6, 2], [1, 3, 7, 5],
+];
+
+fn edge_between(a: usize, b: usize) -> u8 {
+    EDGES.iter().position(|&e| e == (a.min(b), a.max(b))).unwrap() as u8
+}
+
+/// How the surface passes through a cube with a given set of inside corners.
+struct Case {
+    /// Triangles as triples of cube edges, where the surface crosses them. Entries from
+    /// [`CENTER`] on stand for the middle of a loop in `centers`.
+    triangles: Vec<[u8; 3]>,
+    /// Loops of crossed edges that are fanned from their middle.
+    centers: Vec<Vec<u8>>,
+}
+
+const CENTER: u8 = 12;
+
+/// The faces of the cube each edge lies on.
+fn faces_of(edge: u8) -> impl Iterator<Item = usize> {
+    let (a, b) = EDGES[edge as usize];
+    (0..6).filter(move |&f| FACES[f].contains(&a) && FACES[f].contains(&b))
+}
+
+/// How the surface passes through the cube for each of the 256 ways its corners can be
+/// inside or outside.
+///
+/// Rather than spelling out the classic table, each case is worked out from the faces
+/// of the cube. On a face, every run of inside corners is cut off by a segment from
+/// the edge where the run starts to the edge where it ends, going counter-clockwise.
+/// A face with two opposite inside corners gets a segment for each, which keeps the
+/// inside corners apart. Neighbouring cubes see the same corners on a shared face and
+/// cut it the same way, so the mesh has no cracks. Every crossed edge starts one
+/// segment and ends another, so the segments link up into loops around the inside
+/// corners, which are fanned into triangles facing away from them.
+///
+/// A fan's diagonals must not lie on a face, where the neighbouring cube could draw the
+/// same edge. Loops without a corner that avoids that are fanned from their middle.
+fn triangle_table() -> &'static [Case] {
+    static TABLE: OnceLock<Vec<Case>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        (0..256usize).map(|case| {
+            let inside = |c: usize| case & (1 << c) != 0;
+
+            // Ordered so the table, and so the triangulation, is the same on every run.
+            let mut next = BTreeMap::new();
+            for face in FACES {
+                for k in 0..4 {
+                    let (before, corner) = (face[(k + 3) % 4], face[k]);
+                    if inside(corner) && !inside(before) {
+                        // Walk to the end of the run of inside corners starting here.
+                        let mut end = k;
+                        while inside(face[(end + 1) % 4]) {
+                            end = (end + 1) % 4;
+                        }
+                        let entry = edge_between(before, corner);
+                        let exit = edge_between(face[end], face[(end + 1) % 4]);
+                        next.insert(entry, exit);
+                    }
+                }
+            }
+
+            let mut result = Case { triangles: Vec::new(), centers: Vec::new() };
+            while let Some((&start, _)) = next.first_key_value() {
+                let mut polygon = vec![start];
+                let mut edge = next.remove(&start).unwrap();
+                while edge != start {
+                    polygon.push(edge);
+                    edge = next.remove(&edge).unwrap();
+                }
+
+                let n = polygon.len();
+                let apex = (0..n).find(|&k| {
+                    (2..n - 1).all(|d| {
+                        let other = polygon[(k + d) % n];
+                        faces_of(polygon[k]).all(|f| faces_of(other).all(|g| f != g))
+                    })
+                });
+                match apex {
+                    Some(k) => {
+                        for d in 1..n - 1 {
+                            result.triangles.push([polygon[k], polygon[(k + d) % n], polygon[(k + d + 1) % n]]);
+                        }
+                    }
+                    None => {
+                        let center = CENTER + result.centers.len() as u8;
+                        for d in 0..n {
+                            result.triangles.push([center, polygon[d], polygon[(d + 1) % n]]);
+                        }
+                        result.centers.push(polygon);
+                    }
+                }
+            }
+            result
+        }).collect()
+    })
+}
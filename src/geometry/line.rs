@@ -0,0 +1,187 @@
+use cgmath::{MetricSpace, Vector3};
+
+use crate::vertex::Vertex;
+
+/// How the open ends of a line are drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineCap {
+    /// The line stops square at its end point.
+    Butt,
+    /// The line goes on past its end point by half its width, squared off.
+    Square,
+    /// The line ends in a half disk around its end point.
+    Round,
+}
+
+/// How consecutive segments of a line meet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineJoin {
+    /// The outer edges are extended until they meet, unless that takes them further than
+    /// the miter limit, in which case the join is beveled.
+    Miter,
+    /// The outer corners are cut off by a straight edge.
+    Bevel,
+    /// The outer corners are rounded off around the shared point.
+    Round,
+}
+
+/// The look of a line drawn as a strip of constant width on screen, with anti-aliased
+/// edges.
+///
+/// ```
+/// use draw3d::geometry::{LineCap, LineJoin, LineStyle};
+///
+/// let style = LineStyle::new(3.).cap(LineCap::Round).join(LineJoin::Round).dashes(0.2, 0.1);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineStyle {
+    /// The width in pixels.
+    pub width: f32,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    /// The longest a miter may reach past the shared point, in half widths.
+    pub miter_limit: f32,
+    /// The lengths of the dashes and of the gaps between them, in the line's own units.
+    pub dashes: Option<[f32; 2]>,
+}
+
+impl LineStyle {
+    /// A solid line `width` pixels wide with butt caps and miter joins.
+    pub fn new(width: f32) -> LineStyle {
+        LineStyle {
+            width,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 4.,
+            dashes: None,
+        }
+    }
+
+    pub fn cap(mut self, cap: LineCap) -> LineStyle {
+        self.cap = cap;
+        self
+    }
+
+    pub fn join(mut self, join: LineJoin) -> LineStyle {
+        self.join = join;
+        self
+    }
+
+    pub fn miter_limit(mut self, limit: f32) -> LineStyle {
+        self.miter_limit = limit.max(1.);
+        self
+    }
+
+    /// Breaks the line into dashes of length `dash` separated by gaps of length `gap`,
+    /// measured along the line in its own units. Dashes end square, whatever the caps.
+    pub fn dashes(mut self, dash: f32, gap: f32) -> LineStyle {
+        self.dashes = Some([dash, gap]);
+        self
+    }
+
+    /// Makes the line solid again.
+    pub fn solid(mut self) -> LineStyle {
+        self.dashes = None;
+        self
+    }
+
+    /// One segment per pair of consecutive vertices, and one back to the first vertex if
+    /// the line is `closed`. Repeated vertices are skipped.
+    pub fn segments(&self, vertices: &[Vertex], closed: bool) -> Vec<LineSegmentRaw> {
+        let mut points: Vec<Vertex> = Vec::with_capacity(vertices.len());
+        for vertex in vertices {
+            if points.last().is_none_or(|last| last.position != vertex.position) {
+                points.push(*vertex);
+            }
+        }
+        if closed && points.len() > 1 && points[0].position == points[points.len() - 1].position {
+            points.pop();
+        }
+        let closed = closed && points.len() > 2;
+        let n = points.len();
+        if n < 2 {
+            return Vec::new();
+        }
+
+        let count = if closed { n } else { n - 1 };
+        let mut distance = 0.;
+        let (cap, join) = (self.cap as u32, self.join as u32);
+        let dash = self.dashes.unwrap_or([0., 0.]);
+        (0..count).map(|i| {
+            let (start, end) = (points[i], points[(i + 1) % n]);
+            let length = Vector3::from(start.position).distance(end.position.into());
+            let joined_start = closed || i > 0;
+            let joined_end = closed || i + 2 < n;
+            let next = if joined_end { points[(i + 2) % n].position } else { end.position };
+            let segment = LineSegmentRaw {
+                start: start.position,
+                end: end.position,
+                next,
+                start_color: start.color,
+                end_color: end.color,
+                distances: [distance, distance + length],
+                width: self.width,
+                miter_limit: self.miter_limit,
+                dash,
+                cap,
+                join,
+                joined: [joined_start as u32, joined_end as u32],
+            };
+            distance += length;
+            segment
+        }).collect()
+    }
+}
+
+impl Default for LineStyle {
+    fn default() -> LineStyle {
+        LineStyle::new(1.)
+    }
+}
+
+/// One segment of a wide line, drawn as an instance of a quad that the vertex shader
+/// spreads over the pixels the segment, its cap or its join can cover.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LineSegmentRaw {
+    pub start: [f32; 3],
+    pub end: [f32; 3],
+    /// The point after `end`, or `end` itself at the end of an open line.
+    pub next: [f32; 3],
+    pub start_color: [f32; 3],
+    pub end_color: [f32; 3],
+    /// The distance along the line to `start` and to `end`.
+    pub distances: [f32; 2],
+    pub width: f32,
+    pub miter_limit: f32,
+    /// Dash and gap lengths, both 0 for a solid line.
+    pub dash: [f32; 2],
+    pub cap: u32,
+    pub join: u32,
+    /// Whether the segment joins another one at its start and at its end.
+    pub joined: [u32; 2],
+}
+
+impl LineSegmentRaw {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![
+        0 => Float32x3,
+        1 => Float32x3,
+        2 => Float32x3,
+        3 => Float32x3,
+        4 => Float32x3,
+        // distances, width and miter_limit
+        5 => Float32x4,
+        6 => Float32x2,
+        // cap, join and joined
+        7 => Uint32x4,
+    ];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<LineSegmentRaw>() as wgpu::BufferAddress,
+            // Every vertex of a segment's quad reads the same segment.
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
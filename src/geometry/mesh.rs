@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use cgmath::{InnerSpace, Matrix, Matrix4, SquareMatrix, Vector3, Vector4};
+
+use super::{Draw, Geometry};
+use crate::app::App;
+use crate::vertex::{self, Vertex};
+
+/// The color given to vertices of meshes that don't have their own.
+pub(crate) const DEFAULT_COLOR: [f32; 3] = [0.6, 0., 0.6];
+
+/// A triangle mesh kept on the CPU, for inspecting and processing geometry before
+/// uploading it, or after tessellating it with [`Draw::mesh`].
+///
+/// Every attribute holds one entry per position, and every three indices make a triangle
+/// winding counter-clockwise when seen from outside. A zero normal draws its vertex unlit.
+///
+/// ```
+/// use draw3d::geometry::Mesh;
+/// use draw3d::geometry::primitives::Cuboid;
+///
+/// let (vertices, indices) = Cuboid::cube(1.).tessellate();
+/// let mut cube = Mesh::from_vertices(&vertices, &indices);
+/// // Each face has its own vertices, for flat shading; weld them into one closed surface.
+/// cube.weld(1e-6).recompute_normals();
+/// assert!(cube.validate().is_ok());
+/// assert_eq!(cube.positions.len(), 8);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub colors: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+}
+
+/// A problem found by [`Mesh::validate`] or [`Mesh::validate_closed`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum MeshError {
+    /// An attribute doesn't have one entry per position.
+    AttributeLength { attribute: &'static str, len: usize, positions: usize },
+    /// The number of indices isn't a multiple of three.
+    IncompleteTriangle { indices: usize },
+    IndexOutOfRange { triangle: usize, index: u32 },
+    /// A triangle repeats a vertex or has no area.
+    DegenerateTriangle { triangle: usize },
+    /// An edge is shared by more than two triangles.
+    NonManifoldEdge { edge: [u32; 2], triangles: usize },
+    /// Two triangles run along an edge in the same direction, so they face opposite ways.
+    InconsistentWinding { edge: [u32; 2] },
+    /// An edge of only one triangle, where a closed mesh has a hole.
+    BoundaryEdge { edge: [u32; 2] },
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::AttributeLength { attribute, len, positions } =>
+                write!(f, "mesh has {} {} for {} positions", len, attribute, positions),
+            MeshError::IncompleteTriangle { indices } =>
+                write!(f, "{} indices don't make whole triangles", indices),
+            MeshError::IndexOutOfRange { triangle, index } =>
+                write!(f, "triangle {} refers to missing vertex {}", triangle, index),
+            MeshError::DegenerateTriangle { triangle } =>
+                write!(f, "triangle {} is degenerate", triangle),
+            MeshError::NonManifoldEdge { edge, triangles } =>
+                write!(f, "edge {}-{} is shared by {} triangles", edge[0], edge[1], triangles),
+            MeshError::InconsistentWinding { edge } =>
+                write!(f, "triangles on either side of edge {}-{} wind the same way", edge[0], edge[1]),
+            MeshError::BoundaryEdge { edge } =>
+                write!(f, "edge {}-{} is on the boundary of a hole", edge[0], edge[1]),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+/// The corners of the smallest axis aligned box holding a mesh.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl BoundingBox {
+    pub fn center(&self) -> [f32; 3] {
+        [0, 1, 2].map(|k| (self.min[k] + self.max[k]) / 2.)
+    }
+
+    pub fn size(&self) -> [f32; 3] {
+        [0, 1, 2].map(|k| self.max[k] - self.min[k])
+    }
+}
+
+impl Mesh {
+    /// A mesh of uncolored vertices without normals or texture coordinates.
+    pub fn new(positions: Vec<[f32; 3]>, indices: Vec<u32>) -> Mesh {
+        let n = positions.len();
+        Mesh {
+            positions,
+            normals: vec![[0.; 3]; n],
+            colors: vec![DEFAULT_COLOR; n],
+            uvs: vec![[0.; 2]; n],
+            indices,
+        }
+    }
+
+    pub fn from_vertices(vertices: &[Vertex], indices: &[u32]) -> Mesh {
+        Mesh {
+            positions: vertices.iter().map(|v| v.position).collect(),
+            normals: vertices.iter().map(|v| v.normal).collect(),
+            colors: vertices.iter().map(|v| v.color).collect(),
+            uvs: vertices.iter().map(|v| v.uv).collect(),
+            indices: indices.to_vec(),
+        }
+    }
+
+    /// The mesh's vertices as drawn. Missing attributes take their defaults.
+    pub fn vertices(&self) -> Vec<Vertex> {
+        (0..self.positions.len()).map(|i| Vertex {
+            position: self.positions[i],
+            color: self.colors.get(i).copied().unwrap_or(DEFAULT_COLOR),
+            normal: self.normals.get(i).copied().unwrap_or([0.; 3]),
+            uv: self.uvs.get(i).copied().unwrap_or([0.; 2]),
+        }).collect()
+    }
+
+    /// Paints every vertex `color`.
+    pub fn color(mut self, color: [f32; 3]) -> Mesh {
+        self.colors = vec![color; self.positions.len()];
+        self
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = [u32; 3]> + '_ {
+        self.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]])
+    }
+
+    /// The triangles whose indices all refer to a position.
+    fn valid_triangles(&self) -> impl Iterator<Item = [u32; 3]> + '_ {
+        self.triangles().filter(|t| t.iter().all(|&i| (i as usize) < self.positions.len()))
+    }
+
+    /// Checks that the mesh is well formed, returning every problem found.
+    pub fn validate(&self) -> Result<(), Vec<MeshError>> {
+        self.check(false)
+    }
+
+    /// Like [`validate`](Mesh::validate), but also reports the edges around holes, so a
+    /// mesh that passes encloses a volume, as 3D printing needs.
+    ///
+    /// Vertices are compared by index, so a mesh whose faces have their own copies of
+    /// shared corners needs [`weld`](Mesh::weld)ing first.
+    pub fn validate_closed(&self) -> Result<(), Vec<MeshError>> {
+        self.check(true)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.validate_closed().is_ok()
+    }
+
+    fn check(&self, closed: bool) -> Result<(), Vec<MeshError>> {
+        let n = self.positions.len();
+        let mut errors = Vec::new();
+        for (attribute, len) in [("normals", self.normals.len()), ("colors", self.colors.len()), ("uvs", self.uvs.len())] {
+            if len != n {
+                errors.push(MeshError::AttributeLength { attribute, len, positions: n });
+            }
+        }
+        if !self.indices.len().is_multiple_of(3) {
+            errors.push(MeshError::IncompleteTriangle { indices: self.indices.len() });
+        }
+
+        // How many triangles run along each edge in each direction.
+        let mut edges: HashMap<[u32; 2], [usize; 2]> = HashMap::new();
+        for (triangle, corners) in self.triangles().enumerate() {
+            if let Some(&index) = corners.iter().find(|&&i| i as usize >= n) {
+                errors.push(MeshError::IndexOutOfRange { triangle, index });
+                continue;
+            }
+            if self.is_degenerate(corners) {
+                errors.push(MeshError::DegenerateTriangle { triangle });
+                continue;
+            }
+            for k in 0..3 {
+                let (a, b) = (corners[k], corners[(k + 1) % 3]);
+                let count = edges.entry([a.min(b), a.max(b)]).or_default();
+                count[(a > b) as usize] += 1;
+            }
+        }
+
+        let mut edges: Vec<_> = edges.into_iter().collect();
+        edges.sort_unstable_by_key(|&(edge, _)| edge);
+        for (edge, [forward, backward]) in edges {
+            if forward + backward > 2 {
+                errors.push(MeshError::NonManifoldEdge { edge, triangles: forward + backward });
+            } else if forward == 2 || backward == 2 {
+                errors.push(MeshError::InconsistentWinding { edge });
+            } else if closed && forward + backward == 1 {
+                errors.push(MeshError::BoundaryEdge { edge });
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    fn is_degenerate(&self, [a, b, c]: [u32; 3]) -> bool {
+        if a == b || b == c || c == a {
+            return true;
+        }
+        let [pa, pb, pc] = [a, b, c].map(|i| Vector3::from(self.positions[i as usize]));
+        let longest = (pb - pa).magnitude2().max((pc - pb).magnitude2()).max((pa - pc).magnitude2());
+        // Relative to the longest edge, so the test doesn't depend on the mesh's scale.
+        (pb - pa).cross(pc - pa).magnitude() <= f32::EPSILON * longest
+    }
+
+    /// Merges vertices less than `tolerance` apart, keeping the attributes of the first
+    /// one, and drops the triangles this collapses. A tolerance of zero merges only
+    /// vertices at exactly the same position. Triangles with indices past the last
+    /// position are dropped too.
+    pub fn weld(&mut self, tolerance: f32) -> &mut Mesh {
+        // The index each vertex maps to in the welded mesh, and the vertices kept.
+        let (remap, kept) = if tolerance > 0. { self.weld_near(tolerance) } else { self.weld_exact() };
+
+        let pick = |attribute: &[[f32; 3]]| kept.iter().filter_map(|&i| attribute.get(i).copied()).collect();
+        self.normals = pick(&self.normals);
+        self.colors = pick(&self.colors);
+        self.uvs = kept.iter().filter_map(|&i| self.uvs.get(i).copied()).collect();
+        self.indices = self.valid_triangles()
+            .map(|t| t.map(|i| remap[i as usize]))
+            .filter(|[a, b, c]| a != b && b != c && c != a)
+            .flatten()
+            .collect();
+        self.positions = pick(&self.positions);
+        self
+    }
+
+    /// Merges vertices at exactly the same position, for a tolerance of zero.
+    fn weld_exact(&self) -> (Vec<u32>, Vec<usize>) {
+        let mut seen: HashMap<[u32; 3], u32> = HashMap::new();
+        let mut kept = Vec::new();
+        let remap = self.positions.iter().enumerate().map(|(i, p)| {
+            // Adding zero turns -0 into 0, which is the same position.
+            *seen.entry(p.map(|x| (x + 0.).to_bits())).or_insert_with(|| {
+                kept.push(i);
+                kept.len() as u32 - 1
+            })
+        }).collect();
+        (remap, kept)
+    }
+
+    /// Merges vertices within `tolerance` of one another, finding them in a grid of cells
+    /// `tolerance` wide.
+    fn weld_near(&self, tolerance: f32) -> (Vec<u32>, Vec<usize>) {
+        // Far away coordinates saturate into the outermost cells, which only makes those
+        // cells slower to search.
+        let key = |p: [f32; 3]| p.map(|x| (x / tolerance).floor() as i64);
+        let mut grid: HashMap<[i64; 3], Vec<u32>> = HashMap::new();
+        let mut remap = Vec::with_capacity(self.positions.len());
+        let mut kept: Vec<usize> = Vec::new();
+        for (i, &p) in self.positions.iter().enumerate() {
+            let [x, y, z] = key(p);
+            let neighbours = (-1..=1).flat_map(|dx: i64| (-1..=1).flat_map(move |dy: i64| (-1..=1).map(move |dz: i64| {
+                [x.saturating_add(dx), y.saturating_add(dy), z.saturating_add(dz)]
+            })));
+            let existing = neighbours
+                .filter_map(|k| grid.get(&k))
+                .flatten()
+                .find(|&&j| {
+                    let q = self.positions[kept[j as usize]];
+                    (Vector3::from(p) - Vector3::from(q)).magnitude() <= tolerance
+                })
+                .copied();
+            match existing {
+                Some(j) => remap.push(j),
+                None => {
+                    let j = kept.len() as u32;
+                    kept.push(i);
+                    grid.entry([x, y, z]).or_default().push(j);
+                    remap.push(j);
+                }
+            }
+        }
+        (remap, kept)
+    }
+
+    /// Replaces the normals with the area weighted average of the normals of the triangles
+    /// around each vertex, leaving out triangles with indices past the last position.
+    pub fn recompute_normals(&mut self) -> &mut Mesh {
+        let mut vertices: Vec<Vertex> = self.positions.iter().map(|&p| Vertex::new(p, DEFAULT_COLOR)).collect();
+        let indices: Vec<u32> = self.valid_triangles().flatten().collect();
+        vertex::compute_normals(&mut vertices, &indices);
+        self.normals = vertices.iter().map(|v| v.normal).collect();
+        self
+    }
+
+    /// The smallest box holding every position, or `None` for an empty mesh.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        let first = *self.positions.first()?;
+        Some(self.positions.iter().fold(BoundingBox { min: first, max: first }, |b, p| BoundingBox {
+            min: [0, 1, 2].map(|k| b.min[k].min(p[k])),
+            max: [0, 1, 2].map(|k| b.max[k].max(p[k])),
+        }))
+    }
+
+    /// Appends the vertices and triangles of `other`.
+    pub fn merge(&mut self, other: &Mesh) -> &mut Mesh {
+        let offset = self.positions.len() as u32;
+        // Fill in missing attributes so they stay one per position.
+        let vertices = self.vertices().into_iter().chain(other.vertices()).collect::<Vec<_>>();
+        let indices: Vec<u32> = self.indices.iter().copied().chain(other.indices.iter().map(|i| i + offset)).collect();
+        *self = Mesh::from_vertices(&vertices, &indices);
+        self
+    }
+
+    /// Moves the mesh by `matrix`, keeping normals perpendicular to their surfaces and
+    /// triangles facing outwards even if the matrix mirrors the mesh.
+    pub fn transform(&mut self, matrix: Matrix4<f32>) -> &mut Mesh {
+        let normal_matrix = matrix.invert().unwrap_or(Matrix4::identity()).transpose();
+        for p in &mut self.positions {
+            *p = (matrix * Vector4::new(p[0], p[1], p[2], 1.)).truncate().into();
+        }
+        for n in &mut self.normals {
+            let v = (normal_matrix * Vector4::new(n[0], n[1], n[2], 0.)).truncate();
+            *n = if v.magnitude2() > 0. { v.normalize().into() } else { [0.; 3] };
+        }
+        if matrix.determinant() < 0. {
+            for t in self.indices.chunks_exact_mut(3) {
+                t.swap(1, 2);
+            }
+        }
+        self
+    }
+
+    /// Uploads the mesh to the GPU.
+    pub fn to_geometry(&self, app: &App) -> Geometry {
+        Geometry::new_mesh(app, &self.vertices(), &self.indices)
+    }
+}
+
+impl Draw for Mesh {
+    fn draw(&self, app: &App) -> Geometry {
+        self.to_geometry(app)
+    }
+
+    fn mesh(&self) -> Option<Mesh> {
+        Some(self.clone())
+    }
+}
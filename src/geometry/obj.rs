@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use cgmath::{InnerSpace, Vector3, Zero};
+
+use super::mesh::DEFAULT_COLOR;
+use super::{Draw, Geometry, Mesh};
+use crate::app::App;
+use crate::vertex::Vertex;
+
+/// The diffuse (`Kd`) color of each material in an MTL file, by name.
+pub type Materials = HashMap<String, [f32; 3]>;
+
+/// A problem reading or writing an OBJ file.
+#[derive(Debug)]
+pub enum ObjError {
+    Io(std::io::Error),
+    /// A line that couldn't be understood, counting from 1.
+    Parse { line: usize, message: String },
+    /// The object written has no triangles, like a line.
+    NoTriangles,
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(error) => write!(f, "{}", error),
+            ObjError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            ObjError::NoTriangles => write!(f, "only triangle meshes can be written as OBJ"),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ObjError {
+    fn from(error: std::io::Error) -> ObjError {
+        ObjError::Io(error)
+    }
+}
+
+/// Faces of an OBJ file that share a group and a material.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjGroup {
+    /// The name given by the last `g` or `o` statement, empty before the first one.
+    pub name: String,
+    pub material: Option<String>,
+    pub mesh: Mesh,
+}
+
+/// The triangles of a Wavefront OBJ file, split into its groups.
+///
+/// Polygons are triangulated by clipping ears, so concave faces keep their outline.
+/// Faces take the diffuse color of their material, or else the colors written after
+/// their vertices' positions. Groups without any normals get them computed from their
+/// faces. Texture coordinates are kept as written, with `v` pointing up.
+///
+/// ```no_run
+/// use draw3d::app::App;
+/// use draw3d::geometry::obj::Obj;
+/// use draw3d::geometry::Geometry;
+///
+/// struct Model {
+///     teapot: Geometry,
+/// }
+///
+/// fn model(app: &App) -> Model {
+///     let teapot = Obj::load("teapot.obj").expect("couldn't load the teapot");
+///     Model { teapot: teapot.to_mesh().to_geometry(app) }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Obj {
+    pub groups: Vec<ObjGroup>,
+}
+
+impl Obj {
+    /// Reads the OBJ file at `path`, with the materials of the MTL files it names next to it.
+    ///
+    /// MTL files that can't be read are skipped with a warning, leaving their faces uncolored.
+    pub fn load(path: impl AsRef<Path>) -> Result<Obj, ObjError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut materials = Materials::new();
+        for line in text.lines() {
+            if let Some(("mtllib", names)) = statement(line) {
+                for name in names.split_whitespace() {
+                    match File::open(dir.join(name)) {
+                        Ok(file) => materials.extend(read_mtl(BufReader::new(file))?),
+                        Err(error) => log::warn!("Couldn't open material library {}: {}", name, error),
+                    }
+                }
+            }
+        }
+        Obj::read(text.as_bytes(), &materials)
+    }
+
+    /// Reads an OBJ file from `reader`. Its `mtllib` statements are ignored; the colors of
+    /// its materials are looked up in `materials` instead.
+    pub fn read(reader: impl BufRead, materials: &Materials) -> Result<Obj, ObjError> {
+        let mut positions: Vec<[f32; 3]> = Vec::new();
+        let mut colors: Vec<Option<[f32; 3]>> = Vec::new();
+        let mut uvs: Vec<[f32; 2]> = Vec::new();
+        let mut normals: Vec<[f32; 3]> = Vec::new();
+        let mut groups = Vec::new();
+        let mut group = GroupBuilder::new(String::new(), None);
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let number = i + 1;
+            let error = |message: String| ObjError::Parse { line: number, message };
+            let Some((keyword, rest)) = statement(&line) else { continue };
+            match keyword {
+                "v" => {
+                    let values = floats(rest).map_err(error)?;
+                    if values.len() < 3 {
+                        return Err(error(format!("a vertex needs 3 coordinates, found {}", values.len())));
+                    }
+                    positions.push([values[0], values[1], values[2]]);
+                    // A fourth value alone is a weight rather than the start of a color.
+                    colors.push((values.len() >= 6).then(|| [values[3], values[4], values[5]]));
+                }
+                "vt" => {
+                    let values = floats(rest).map_err(error)?;
+                    if values.is_empty() {
+                        return Err(error("a texture coordinate needs at least 1 value".to_string()));
+                    }
+                    uvs.push([values[0], values.get(1).copied().unwrap_or(0.)]);
+                }
+                "vn" => {
+                    let values = floats(rest).map_err(error)?;
+                    if values.len() < 3 {
+                        return Err(error(format!("a normal needs 3 coordinates, found {}", values.len())));
+                    }
+                    normals.push([values[0], values[1], values[2]]);
+                }
+                "f" => {
+                    let mut corners = Vec::new();
+                    for corner in rest.split_whitespace() {
+                        let mut parts = corner.split('/');
+                        let mut index = |len: usize| -> Result<Option<usize>, ObjError> {
+                            match parts.next().filter(|s| !s.is_empty()) {
+                                Some(s) => resolve(s, len).map(Some).map_err(error),
+                                None => Ok(None),
+                            }
+                        };
+                        let position = index(positions.len())?
+                            .ok_or_else(|| error(format!("face corner {:?} has no vertex", corner)))?;
+                        corners.push((position, index(uvs.len())?, index(normals.len())?));
+                    }
+                    if corners.len() < 3 {
+                        return Err(error(format!("a face needs 3 corners, found {}", corners.len())));
+                    }
+                    let outline: Vec<Vector3<f32>> = corners.iter().map(|c| positions[c.0].into()).collect();
+                    let corners: Vec<u32> = corners.iter().map(|&(p, t, n)| {
+                        let color = group.material.as_ref().and_then(|m| materials.get(m)).copied()
+                            .or(colors[p])
+                            .unwrap_or(DEFAULT_COLOR);
+                        group.vertex((p, t, n), || {
+                            let vertex = Vertex::new(positions[p], color);
+                            let vertex = match t { Some(t) => vertex.with_uv(uvs[t]), None => vertex };
+                            match n { Some(n) => Vertex { normal: normals[n], ..vertex }, None => vertex }
+                        })
+                    }).collect();
+                    for [a, b, c] in triangulate(&outline) {
+                        group.indices.extend([corners[a], corners[b], corners[c]]);
+                    }
+                }
+                "g" | "o" => {
+                    let next = GroupBuilder::new(rest.to_string(), group.material.clone());
+                    groups.push(std::mem::replace(&mut group, next));
+                }
+                "usemtl" => {
+                    let next = GroupBuilder::new(group.name.clone(), Some(rest.to_string()));
+                    groups.push(std::mem::replace(&mut group, next));
+                }
+                // Lines, points, smoothing groups, material libraries and the rest don't
+                // change the triangles.
+                _ => {}
+            }
+        }
+        groups.push(group);
+
+        Ok(Obj { groups: groups.into_iter().filter_map(GroupBuilder::finish).collect() })
+    }
+
+    /// The first group called `name`.
+    pub fn group(&self, name: &str) -> Option<&ObjGroup> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+
+    /// Every group merged into one mesh.
+    pub fn to_mesh(&self) -> Mesh {
+        let mut mesh = Mesh::default();
+        for group in &self.groups {
+            mesh.merge(&group.mesh);
+        }
+        mesh
+    }
+
+    /// Writes the groups as OBJ, each vertex with its color after its position.
+    ///
+    /// No MTL file is written: the colors of materials are already those of the vertices.
+    pub fn write(&self, mut writer: impl Write) -> Result<(), ObjError> {
+        if self.groups.iter().all(|g| g.mesh.indices.is_empty()) {
+            return Err(ObjError::NoTriangles);
+        }
+        writeln!(writer, "# Written by draw3d")?;
+        // Groups that leave out texture coordinates or normals don't number any, so each
+        // kind of index runs on by itself.
+        let [mut positions, mut uv_offset, mut normal_offset] = [1; 3];
+        for group in &self.groups {
+            let mesh = &group.mesh;
+            let vertices = mesh.vertices();
+            // Attributes left at their defaults aren't worth writing.
+            let uvs = vertices.iter().any(|v| v.uv != [0.; 2]);
+            let normals = vertices.iter().any(|v| v.normal != [0.; 3]);
+
+            if !group.name.is_empty() {
+                writeln!(writer, "g {}", group.name)?;
+            }
+            for v in &vertices {
+                let [x, y, z] = v.position;
+                let [r, g, b] = v.color;
+                writeln!(writer, "v {} {} {} {} {} {}", x, y, z, r, g, b)?;
+            }
+            if uvs {
+                for v in &vertices {
+                    writeln!(writer, "vt {} {}", v.uv[0], v.uv[1])?;
+                }
+            }
+            if normals {
+                for v in &vertices {
+                    let [x, y, z] = v.normal;
+                    writeln!(writer, "vn {} {} {}", x, y, z)?;
+                }
+            }
+            for triangle in mesh.triangles() {
+                write!(writer, "f")?;
+                for i in triangle {
+                    let (v, vt, vn) = (i + positions, i + uv_offset, i + normal_offset);
+                    match (uvs, normals) {
+                        (false, false) => write!(writer, " {}", v)?,
+                        (true, false) => write!(writer, " {}/{}", v, vt)?,
+                        (false, true) => write!(writer, " {}//{}", v, vn)?,
+                        (true, true) => write!(writer, " {}/{}/{}", v, vt, vn)?,
+                    }
+                }
+                writeln!(writer)?;
+            }
+            let count = vertices.len() as u32;
+            positions += count;
+            uv_offset += if uvs { count } else { 0 };
+            normal_offset += if normals { count } else { 0 };
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ObjError> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl From<Mesh> for Obj {
+    fn from(mesh: Mesh) -> Obj {
+        Obj { groups: vec![ObjGroup { name: String::new(), material: None, mesh }] }
+    }
+}
+
+impl Draw for Obj {
+    fn draw(&self, app: &App) -> Geometry {
+        self.to_mesh().to_geometry(app)
+    }
+
+    fn mesh(&self) -> Option<Mesh> {
+        Some(self.to_mesh())
+    }
+}
+
+/// Writes the triangles `object` tessellates into as OBJ.
+///
+/// ```no_run
+/// use draw3d::geometry::obj;
+/// use draw3d::geometry::primitives::Torus;
+///
+/// obj::save("torus.obj", &Torus::new(1., 0.3)).unwrap();
+/// ```
+pub fn write(writer: impl Write, object: &dyn Draw) -> Result<(), ObjError> {
+    Obj::from(object.mesh().ok_or(ObjError::NoTriangles)?).write(writer)
+}
+
+pub fn save(path: impl AsRef<Path>, object: &dyn Draw) -> Result<(), ObjError> {
+    write(BufWriter::new(File::create(path)?), object)
+}
+
+/// Reads the diffuse colors of the materials in an MTL file.
+pub fn read_mtl(reader: impl BufRead) -> Result<Materials, ObjError> {
+    let mut materials = Materials::new();
+    let mut current = None;
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let error = |message: String| ObjError::Parse { line: i + 1, message };
+        match statement(&line) {
+            Some(("newmtl", name)) => {
+                materials.insert(name.to_string(), DEFAULT_COLOR);
+                current = Some(name.to_string());
+            }
+            Some(("Kd", rest)) => {
+                let values = floats(rest).map_err(error)?;
+                let name = current.as_ref().ok_or_else(|| error("a color before any newmtl".to_string()))?;
+                if values.len() < 3 {
+                    return Err(error(format!("a color needs 3 values, found {}", values.len())));
+                }
+                materials.insert(name.clone(), [values[0], values[1], values[2]]);
+            }
+            _ => {}
+        }
+    }
+    Ok(materials)
+}
+
+struct GroupBuilder {
+    name: String,
+    material: Option<String>,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    /// The vertex made for each combination of position, texture coordinate and normal.
+    lookup: HashMap<(usize, Option<usize>, Option<usize>), u32>,
+    has_normals: bool,
+}
+
+impl GroupBuilder {
+    fn new(name: String, material: Option<String>) -> GroupBuilder {
+        GroupBuilder { name, material, vertices: Vec::new(), indices: Vec::new(), lookup: HashMap::new(), has_normals: false }
+    }
+
+    fn vertex(&mut self, key: (usize, Option<usize>, Option<usize>), make: impl FnOnce() -> Vertex) -> u32 {
+        self.has_normals |= key.2.is_some();
+        *self.lookup.entry(key).or_insert_with(|| {
+            self.vertices.push(make());
+            self.vertices.len() as u32 - 1
+        })
+    }
+
+    fn finish(self) -> Option<ObjGroup> {
+        if self.indices.is_empty() {
+            return None;
+        }
+        let mut mesh = Mesh::from_vertices(&self.vertices, &self.indices);
+        if !self.has_normals {
+            mesh.recompute_normals();
+        }
+        Some(ObjGroup { name: self.name, material: self.material, mesh })
+    }
+}
+
+/// The keyword of a line and the rest of it, without comments.
+fn statement(line: &str) -> Option<(&str, &str)> {
+    let line = line.split('#').next().unwrap_or("").trim();
+    let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    (!keyword.is_empty()).then_some((keyword, rest.trim()))
+}
+
+fn floats(s: &str) -> Result<Vec<f32>, String> {
+    s.split_whitespace()
+        .map(|x| x.parse().map_err(|_| format!("{:?} isn't a number", x)))
+        .collect()
+}
+
+/// Turns a 1 based index, or a negative one counting back from the end, into a 0 based one.
+fn resolve(s: &str, len: usize) -> Result<usize, String> {
+    let i: i64 = s.parse().map_err(|_| format!("{:?} isn't an index", s))?;
+    let index = if i < 0 { len as i64 + i } else { i - 1 };
+    if i == 0 || index < 0 || index >= len as i64 {
+        return Err(format!("index {} is out of range with {} defined", i, len));
+    }
+    Ok(index as usize)
+}
+
+/// Splits a polygon into triangles by clipping ears, so concave polygons stay inside their
+/// outline. Whatever is left of an outline without ears, like a self intersecting one, is
+/// fanned instead.
+fn triangulate(outline: &[Vector3<f32>]) -> Vec<[usize; 3]> {
+    // Newell's method, which gives a sensible normal for polygons that aren't quite flat.
+    let mut normal = Vector3::zero();
+    for (i, a) in outline.iter().enumerate() {
+        let b = outline[(i + 1) % outline.len()];
+        normal += Vector3::new((a.y - b.y) * (a.z + b.z), (a.z - b.z) * (a.x + b.x), (a.x - b.x) * (a.y + b.y));
+    }
+    let turn = |a: usize, b: usize, p: usize| (outline[b] - outline[a]).cross(outline[p] - outline[a]).dot(normal);
+
+    let mut remaining: Vec<usize> = (0..outline.len()).collect();
+    let mut triangles = Vec::new();
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |i: usize| [remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]];
+        let ear = (0..n).find(|&i| {
+            let [a, b, c] = corner(i);
+            turn(a, b, c) > 0. && remaining.iter()
+                .filter(|&&p| p != a && p != b && p != c)
+                .all(|&p| turn(a, b, p) < 0. || turn(b, c, p) < 0. || turn(c, a, p) < 0.)
+        });
+        let Some(i) = ear else { break };
+        triangles.push(corner(i));
+        remaining.remove(i);
+    }
+    for k in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[k], remaining[k + 1]]);
+    }
+    triangles
+}
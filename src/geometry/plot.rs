@@ -0,0 +1,257 @@
+use cgmath::{InnerSpace, Vector3};
+
+use super::{Coloring, Domain, Draw, Geometry, Mesh};
+use crate::app::App;
+use crate::colormap::Colormap;
+use crate::vertex::Vertex;
+
+/// A height as a function of `x` and `y`.
+pub type HeightFn = Box<dyn Fn(f32, f32) -> f32 + Send + Sync>;
+
+enum Heights {
+    Function { f: HeightFn, x: Domain, y: Domain },
+    /// Samples with x varying fastest.
+    Grid { values: Vec<f32>, dims: [usize; 2], x: [f32; 2], y: [f32; 2] },
+}
+
+/// The graph of `z = f(x, y)`, or of a grid of heights, colored through a colormap.
+///
+/// Samples whose height isn't finite leave a hole in the plot. The [`Coloring`] of a plot
+/// gets `x` and `y` as its parameters.
+///
+/// ```
+/// use draw3d::colormap::Colormap;
+/// use draw3d::geometry::{Domain, SurfacePlot};
+///
+/// let range = Domain::linspace(-1., 1., 50);
+/// let saddle = SurfacePlot::new(|x, y| x * x - y * y, range.clone(), range)
+///     .colormap(Colormap::coolwarm());
+/// ```
+pub struct SurfacePlot {
+    heights: Heights,
+    coloring: Coloring,
+}
+
+impl SurfacePlot {
+    pub fn new<F>(f: F, x: impl Into<Domain>, y: impl Into<Domain>) -> SurfacePlot
+    where
+        F: Fn(f32, f32) -> f32 + Send + Sync + 'static,
+    {
+        SurfacePlot::with_heights(Heights::Function { f: Box::new(f), x: x.into(), y: y.into() })
+    }
+
+    /// A plot of already sampled heights spread evenly over `x[0]..=x[1]` and `y[0]..=y[1]`.
+    /// `values` holds `dims[0] * dims[1]` heights with x varying fastest.
+    pub fn from_grid(values: Vec<f32>, dims: [usize; 2], x: [f32; 2], y: [f32; 2]) -> SurfacePlot {
+        assert_eq!(values.len(), dims[0] * dims[1], "grid has the wrong number of values");
+        SurfacePlot::with_heights(Heights::Grid { values, dims, x, y })
+    }
+
+    fn with_heights(heights: Heights) -> SurfacePlot {
+        SurfacePlot {
+            heights,
+            coloring: Coloring::by_scalar(|_, _, [_, _, z]| z, Colormap::default()),
+        }
+    }
+
+    /// Replaces the coloring by height.
+    pub fn coloring(mut self, coloring: impl Into<Coloring>) -> SurfacePlot {
+        self.coloring = coloring.into();
+        self
+    }
+
+    pub fn colormap(mut self, colormap: Colormap) -> SurfacePlot {
+        self.coloring = self.coloring.with_colormap(colormap);
+        self
+    }
+
+    /// Colors the plot by a scalar of the position on it, rather than by height.
+    pub fn color_by<F>(mut self, scalar: F) -> SurfacePlot
+    where
+        F: Fn([f32; 3]) -> f32 + Send + Sync + 'static,
+    {
+        self.coloring = self.coloring.with_scalar_fn(Box::new(move |_, _, p| scalar(p)));
+        self
+    }
+
+    /// Colors the plot by one value per sample, laid out like the samples of the grid.
+    ///
+    /// # Panics
+    ///
+    /// If there isn't exactly one value per sample.
+    pub fn color_values(mut self, values: Vec<f32>) -> SurfacePlot {
+        assert_eq!(values.len(), self.sample_count(), "need one color value per sample");
+        self.coloring = self.coloring.with_values(values);
+        self
+    }
+
+    /// The scalars mapped to the ends of the colormap. By default these are the smallest
+    /// and largest scalar on the plot.
+    pub fn range(mut self, min: f32, max: f32) -> SurfacePlot {
+        self.coloring = self.coloring.range(min, max);
+        self
+    }
+
+    fn sample_count(&self) -> usize {
+        match &self.heights {
+            Heights::Function { x, y, .. } => x.len() * y.len(),
+            Heights::Grid { dims, .. } => dims[0] * dims[1],
+        }
+    }
+
+    /// The x and y coordinates of the samples, and the heights with x varying fastest.
+    fn samples(&self) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+        match &self.heights {
+            Heights::Function { f, x, y } => {
+                let (xs, ys) = (x.values(), y.values());
+                let zs = ys.iter().flat_map(|&y| xs.iter().map(move |&x| f(x, y))).collect();
+                (xs, ys, zs)
+            }
+            Heights::Grid { values, dims, x, y } => {
+                let xs = Domain::linspace(x[0], x[1], dims[0]).values();
+                let ys = Domain::linspace(y[0], y[1], dims[1]).values();
+                (xs, ys, values.clone())
+            }
+        }
+    }
+
+    /// The scalars mapped to the ends of the colormap, for labelling a [`Colorbar`].
+    /// This is `[0, 1]` if no scalar is finite, or if the plot isn't colored by scalar.
+    pub fn value_range(&self) -> [f32; 2] {
+        let (xs, ys, zs) = self.samples();
+        let positions = grid_positions(&xs, &ys, &zs);
+        self.coloring.scalar_range(&grid_parameters(&xs, &ys), &positions).unwrap_or([0., 1.])
+    }
+
+    /// A colorbar showing the plot's colormap, or the default one if the plot isn't
+    /// colored by scalar.
+    pub fn colorbar(&self) -> Colorbar {
+        Colorbar::new(self.coloring.colormap().cloned().unwrap_or_default())
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let (xs, ys, zs) = self.samples();
+        let (nx, ny) = (xs.len(), ys.len());
+        if nx < 2 || ny < 2 {
+            return (Vec::new(), Vec::new());
+        }
+        let positions = grid_positions(&xs, &ys, &zs);
+        let colors = self.coloring.colors(&grid_parameters(&xs, &ys), &positions);
+
+        let index = |i: usize, j: usize| j * nx + i;
+        let vertices = positions.iter().zip(colors).enumerate().map(|(k, (&position, color))| {
+            let (i, j) = (k % nx, k / nx);
+            // Central differences, one sided at the edges of the grid.
+            let (i0, i1) = (i.saturating_sub(1), (i + 1).min(nx - 1));
+            let (j0, j1) = (j.saturating_sub(1), (j + 1).min(ny - 1));
+            let dx = (zs[index(i1, j)] - zs[index(i0, j)]) / (xs[i1] - xs[i0]);
+            let dy = (zs[index(i, j1)] - zs[index(i, j0)]) / (ys[j1] - ys[j0]);
+            let normal = Vector3::new(-dx, -dy, 1.).normalize();
+            let normal = if normal.x.is_finite() && normal.y.is_finite() { normal } else { Vector3::unit_z() };
+
+            let uv = [i as f32 / (nx - 1) as f32, j as f32 / (ny - 1) as f32];
+            Vertex::with_normal(position, color, normal.into()).with_uv(uv)
+        }).collect();
+
+        let finite = |k: usize| zs[k].is_finite();
+        let mut indices = Vec::new();
+        for j in 0..ny - 1 {
+            for i in 0..nx - 1 {
+                let [a, b, c, d] = [index(i, j), index(i + 1, j), index(i + 1, j + 1), index(i, j + 1)];
+                if [a, b, c, d].into_iter().all(finite) {
+                    indices.extend([a, b, c, a, c, d].map(|k| k as u32));
+                }
+            }
+        }
+
+        (vertices, indices)
+    }
+}
+
+fn grid_positions(xs: &[f32], ys: &[f32], zs: &[f32]) -> Vec<[f32; 3]> {
+    ys.iter()
+        .flat_map(|&y| xs.iter().map(move |&x| (x, y)))
+        .zip(zs.iter())
+        .map(|((x, y), &z)| [x, y, z])
+        .collect()
+}
+
+fn grid_parameters(xs: &[f32], ys: &[f32]) -> Vec<[f32; 2]> {
+    ys.iter().flat_map(|&y| xs.iter().map(move |&x| [x, y])).collect()
+}
+
+impl Draw for SurfacePlot {
+    fn draw(&self, app: &App) -> Geometry {
+        let (vertices, indices) = self.tessellate();
+        Geometry::new_mesh(app, &vertices, &indices)
+    }
+
+    fn mesh(&self) -> Option<Mesh> {
+        let (vertices, indices) = self.tessellate();
+        Some(Mesh::from_vertices(&vertices, &indices))
+    }
+}
+
+/// A vertical bar showing a colormap from its low end at the bottom to its high end at
+/// the top, drawn over the scene at a fixed place on screen.
+///
+/// Position and size are in normalized device coordinates, where the window spans
+/// `-1` to `1` both ways.
+#[derive(Clone, Debug)]
+pub struct Colorbar {
+    colormap: Colormap,
+    position: [f32; 2],
+    size: [f32; 2],
+    steps: u32,
+}
+
+impl Colorbar {
+    /// A colorbar along the right edge of the window.
+    pub fn new(colormap: Colormap) -> Colorbar {
+        Colorbar {
+            colormap,
+            position: [0.85, -0.8],
+            size: [0.05, 1.6],
+            steps: 64,
+        }
+    }
+
+    /// Moves the bottom left corner of the bar.
+    pub fn position(mut self, x: f32, y: f32) -> Colorbar {
+        self.position = [x, y];
+        self
+    }
+
+    pub fn size(mut self, width: f32, height: f32) -> Colorbar {
+        self.size = [width, height];
+        self
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let [x, y] = self.position;
+        let [width, height] = self.size;
+        // Vertices without normals are drawn unlit, so the colors show as they are.
+        let vertices = (0..=self.steps).flat_map(|k| {
+            let t = k as f32 / self.steps as f32;
+            let color = self.colormap.map(t);
+            [
+                Vertex::new([x, y + t * height, 0.], color).with_uv([0., t]),
+                Vertex::new([x + width, y + t * height, 0.], color).with_uv([1., t]),
+            ]
+        }).collect();
+        let indices = (0..self.steps).flat_map(|k| {
+            let a = 2 * k;
+            [a, a + 1, a + 3, a, a + 3, a + 2]
+        }).collect();
+        (vertices, indices)
+    }
+}
+
+impl Draw for Colorbar {
+    fn draw(&self, app: &App) -> Geometry {
+        let (vertices, indices) = self.tessellate();
+        let mut geometry = Geometry::new_mesh(app, &vertices, &indices);
+        geometry.set_screen_space(true).set_depth_write(false);
+        geometry
+    }
+}
@@ -0,0 +1,609 @@
+//! Ready made shapes with normals and texture coordinates.
+//!
+//! Shapes are centered on the origin with their axis along z, which is the camera's
+//! default up direction. Round shapes duplicate the vertices along their seam so the
+//! texture coordinates can run from 0 to 1 around them.
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use cgmath::{ElementWise, InnerSpace, Quaternion, Rotation, Vector3};
+
+use super::mesh::DEFAULT_COLOR;
+use super::{Draw, Geometry, Mesh};
+use crate::app::App;
+use crate::vertex::Vertex;
+
+/// A point of the outline that is swept around the z axis by [`MeshBuilder::revolve`].
+#[derive(Clone, Copy)]
+struct ProfilePoint {
+    r: f32,
+    z: f32,
+    /// The outward normal in the `(r, z)` plane.
+    normal: [f32; 2],
+    /// The texture coordinate along the profile.
+    v: f32,
+}
+
+impl ProfilePoint {
+    fn new(r: f32, z: f32, normal: [f32; 2], v: f32) -> ProfilePoint {
+        ProfilePoint { r, z, normal, v }
+    }
+}
+
+struct MeshBuilder {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    color: [f32; 3],
+}
+
+impl MeshBuilder {
+    fn new(color: [f32; 3]) -> MeshBuilder {
+        MeshBuilder { vertices: Vec::new(), indices: Vec::new(), color }
+    }
+
+    fn vertex(&mut self, position: [f32; 3], normal: [f32; 3], uv: [f32; 2]) -> u32 {
+        self.vertices.push(Vertex::with_normal(position, self.color, normal).with_uv(uv));
+        self.vertices.len() as u32 - 1
+    }
+
+    /// Adds the counter-clockwise quad `a b c d` as two triangles.
+    fn quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+        self.indices.extend_from_slice(&[a, b, c, a, c, d]);
+    }
+
+    /// Sweeps `profile` once around the z axis in `segments` steps.
+    ///
+    /// The surface faces outwards when the profile is walked with its outside on the
+    /// right, e.g. from the bottom to the top of a sphere.
+    fn revolve(&mut self, profile: &[ProfilePoint], segments: u32) {
+        let first = self.vertices.len() as u32;
+        let columns = segments + 1;
+        for point in profile {
+            for s in 0..columns {
+                let theta = 2. * PI * s as f32 / segments as f32;
+                let (sin, cos) = theta.sin_cos();
+                let [nr, nz] = point.normal;
+                self.vertex(
+                    [point.r * cos, point.r * sin, point.z],
+                    [nr * cos, nr * sin, nz],
+                    [s as f32 / segments as f32, point.v],
+                );
+            }
+        }
+
+        for (k, rows) in profile.windows(2).enumerate() {
+            for s in 0..segments {
+                let a = first + k as u32 * columns + s;
+                let [b, c, d] = [a + 1, a + columns + 1, a + columns];
+                // Rows on the axis would give triangles without area.
+                if rows[0].r > 0. {
+                    self.indices.extend_from_slice(&[a, b, c]);
+                }
+                if rows[1].r > 0. {
+                    self.indices.extend_from_slice(&[a, c, d]);
+                }
+            }
+        }
+    }
+
+    /// A flat disk at height `z` facing up or down, with planar texture coordinates.
+    fn cap(&mut self, radius: f32, z: f32, up: bool, segments: u32) {
+        let normal = [0., 0., if up { 1. } else { -1. }];
+        let center = self.vertex([0., 0., z], normal, [0.5, 0.5]);
+        for s in 0..segments {
+            let (sin, cos) = (2. * PI * s as f32 / segments as f32).sin_cos();
+            self.vertex([radius * cos, radius * sin, z], normal, [0.5 + cos / 2., 0.5 + sin / 2.]);
+        }
+        for s in 0..segments {
+            let a = center + 1 + s;
+            let b = center + 1 + (s + 1) % segments;
+            if up {
+                self.indices.extend_from_slice(&[center, a, b]);
+            } else {
+                self.indices.extend_from_slice(&[center, b, a]);
+            }
+        }
+    }
+
+    fn build(self) -> (Vec<Vertex>, Vec<u32>) {
+        (self.vertices, self.indices)
+    }
+}
+
+/// Points on an arc of a circle of `radius` from the polar angle `from` to `to`, centered
+/// at height `z`, with texture coordinates interpolated from `v0` to `v1`.
+fn arc(radius: f32, z: f32, from: f32, to: f32, steps: u32, v0: f32, v1: f32) -> impl Iterator<Item = ProfilePoint> {
+    (0..=steps).map(move |k| {
+        let f = k as f32 / steps as f32;
+        let (sin, cos) = (from + (to - from) * f).sin_cos();
+        ProfilePoint::new(radius * sin, z + radius * cos, [sin, cos], v0 + (v1 - v0) * f)
+    })
+}
+
+/// A sphere made of `rings` bands of latitude split into `segments` around the z axis.
+#[derive(Clone, Copy, Debug)]
+pub struct UvSphere {
+    radius: f32,
+    segments: u32,
+    rings: u32,
+    color: [f32; 3],
+}
+
+impl UvSphere {
+    pub fn new(radius: f32) -> UvSphere {
+        UvSphere { radius, segments: 32, rings: 16, color: DEFAULT_COLOR }
+    }
+
+    pub fn segments(mut self, segments: u32) -> UvSphere {
+        self.segments = segments.max(3);
+        self
+    }
+
+    pub fn rings(mut self, rings: u32) -> UvSphere {
+        self.rings = rings.max(2);
+        self
+    }
+
+    pub fn color(mut self, color: [f32; 3]) -> UvSphere {
+        self.color = color;
+        self
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let mut mesh = MeshBuilder::new(self.color);
+        let profile: Vec<_> = arc(self.radius, 0., PI, 0., self.rings, 0., 1.).collect();
+        mesh.revolve(&profile, self.segments);
+        mesh.build()
+    }
+}
+
+/// A sphere made by repeatedly splitting the faces of an icosahedron, which spreads
+/// its triangles more evenly than a [`UvSphere`].
+///
+/// Texture coordinates are longitude and latitude, and wrap around discontinuously
+/// where the longitude does.
+#[derive(Clone, Copy, Debug)]
+pub struct Icosphere {
+    radius: f32,
+    subdivisions: u32,
+    color: [f32; 3],
+}
+
+impl Icosphere {
+    pub fn new(radius: f32) -> Icosphere {
+        Icosphere { radius, subdivisions: 2, color: DEFAULT_COLOR }
+    }
+
+    /// Each subdivision splits every triangle into four.
+    pub fn subdivisions(mut self, subdivisions: u32) -> Icosphere {
+        self.subdivisions = subdivisions;
+        self
+    }
+
+    pub fn color(mut self, color: [f32; 3]) -> Icosphere {
+        self.color = color;
+        self
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let t = (1. + 5f32.sqrt()) / 2.;
+        #[rustfmt::skip]
+        let mut points: Vec<Vector3<f32>> = [
+            [-1., t, 0.], [1., t, 0.], [-1., -t, 0.], [1., -t, 0.],
+            [0., -1., t], [0., 1., t], [0., -1., -t], [0., 1., -t],
+            [t, 0., -1.], [t, 0., 1.], [-t, 0., -1.], [-t, 0., 1.],
+        ].into_iter().map(|p| Vector3::from(p).normalize()).collect();
+        #[rustfmt::skip]
+        let mut faces: Vec<[u32; 3]> = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        for _ in 0..self.subdivisions {
+            // Edges shared by two faces get a single midpoint.
+            let mut midpoints = HashMap::new();
+            let mut midpoint = |a: u32, b: u32| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    points.push((points[a as usize] + points[b as usize]).normalize());
+                    points.len() as u32 - 1
+                })
+            };
+            faces = faces.into_iter().flat_map(|[a, b, c]| {
+                let [ab, bc, ca] = [midpoint(a, b), midpoint(b, c), midpoint(c, a)];
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            }).collect();
+        }
+
+        let vertices = points.iter().map(|p| {
+            let uv = [0.5 + p.y.atan2(p.x) / (2. * PI), p.z.clamp(-1., 1.).acos() / PI];
+            Vertex::with_normal((p * self.radius).into(), self.color, (*p).into()).with_uv(uv)
+        }).collect();
+        (vertices, faces.concat())
+    }
+}
+
+/// A box with the given edge lengths along x, y and z. Each face has its own vertices
+/// and texture coordinates from 0 to 1.
+#[derive(Clone, Copy, Debug)]
+pub struct Cuboid {
+    size: [f32; 3],
+    color: [f32; 3],
+}
+
+impl Cuboid {
+    pub fn new(size: [f32; 3]) -> Cuboid {
+        Cuboid { size, color: DEFAULT_COLOR }
+    }
+
+    pub fn cube(size: f32) -> Cuboid {
+        Cuboid::new([size; 3])
+    }
+
+    pub fn color(mut self, color: [f32; 3]) -> Cuboid {
+        self.color = color;
+        self
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let half = Vector3::from(self.size) / 2.;
+        let (x, y, z) = (Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z());
+        // Each face's normal is the cross product of its u and v directions.
+        let faces = [(x, y, z), (-x, -y, z), (y, -x, z), (-y, x, z), (z, x, y), (-z, x, -y)];
+
+        let mut mesh = MeshBuilder::new(self.color);
+        for (normal, u, v) in faces {
+            let [center, u, v] = [normal, u, v].map(|axis| axis.mul_element_wise(half));
+            let corners = [(-1., -1.), (1., -1.), (1., 1.), (-1., 1.)].map(|(s, t)| {
+                mesh.vertex((center + u * s + v * t).into(), normal.into(), [(s + 1.) / 2., (1. - t) / 2.])
+            });
+            mesh.quad(corners[0], corners[1], corners[2], corners[3]);
+        }
+        mesh.build()
+    }
+}
+
+/// A cylinder of the given radius and height, optionally closed at both ends.
+#[derive(Clone, Copy, Debug)]
+pub struct Cylinder {
+    radius: f32,
+    height: f32,
+    segments: u32,
+    caps: bool,
+    color: [f32; 3],
+}
+
+impl Cylinder {
+    pub fn new(radius: f32, height: f32) -> Cylinder {
+        Cylinder { radius, height, segments: 32, caps: true, color: DEFAULT_COLOR }
+    }
+
+    pub fn segments(mut self, segments: u32) -> Cylinder {
+        self.segments = segments.max(3);
+        self
+    }
+
+    /// Whether the ends are closed off. They are by default.
+    pub fn caps(mut self, caps: bool) -> Cylinder {
+        self.caps = caps;
+        self
+    }
+
+    pub fn color(mut self, color: [f32; 3]) -> Cylinder {
+        self.color = color;
+        self
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let (r, h) = (self.radius, self.height / 2.);
+        let mut mesh = MeshBuilder::new(self.color);
+        mesh.revolve(&[ProfilePoint::new(r, -h, [1., 0.], 0.), ProfilePoint::new(r, h, [1., 0.], 1.)], self.segments);
+        if self.caps {
+            mesh.cap(r, -h, false, self.segments);
+            mesh.cap(r, h, true, self.segments);
+        }
+        mesh.build()
+    }
+}
+
+/// A cone standing on a base of the given radius with its tip `height` above it.
+#[derive(Clone, Copy, Debug)]
+pub struct Cone {
+    radius: f32,
+    height: f32,
+    segments: u32,
+    color: [f32; 3],
+}
+
+impl Cone {
+    pub fn new(radius: f32, height: f32) -> Cone {
+        Cone { radius, height, segments: 32, color: DEFAULT_COLOR }
+    }
+
+    pub fn segments(mut self, segments: u32) -> Cone {
+        self.segments = segments.max(3);
+        self
+    }
+
+    pub fn color(mut self, color: [f32; 3]) -> Cone {
+        self.color = color;
+        self
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let mut mesh = MeshBuilder::new(self.color);
+        cone(&mut mesh, self.radius, -self.height / 2., self.height / 2., self.segments);
+        mesh.cap(self.radius, -self.height / 2., false, self.segments);
+        mesh.build()
+    }
+}
+
+/// The slanted side of a cone with its base at `bottom` and its tip at `top`.
+fn cone(mesh: &mut MeshBuilder, radius: f32, bottom: f32, top: f32, segments: u32) {
+    let normal = Vector3::new(top - bottom, radius, 0.).normalize();
+    let normal = [normal.x, normal.y];
+    mesh.revolve(&[ProfilePoint::new(radius, bottom, normal, 0.), ProfilePoint::new(0., top, normal, 1.)], segments);
+}
+
+/// A cylinder with hemispherical ends. `length` is the length of the straight part,
+/// so the capsule is `length + 2 * radius` long in total.
+#[derive(Clone, Copy, Debug)]
+pub struct Capsule {
+    radius: f32,
+    length: f32,
+    segments: u32,
+    rings: u32,
+    color: [f32; 3],
+}
+
+impl Capsule {
+    pub fn new(radius: f32, length: f32) -> Capsule {
+        Capsule { radius, length, segments: 32, rings: 8, color: DEFAULT_COLOR }
+    }
+
+    pub fn segments(mut self, segments: u32) -> Capsule {
+        self.segments = segments.max(3);
+        self
+    }
+
+    /// The number of bands of latitude on each end.
+    pub fn rings(mut self, rings: u32) -> Capsule {
+        self.rings = rings.max(1);
+        self
+    }
+
+    pub fn color(mut self, color: [f32; 3]) -> Capsule {
+        self.color = color;
+        self
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let (r, h) = (self.radius, self.length / 2.);
+        // Texture coordinates are spread along the profile by arc length.
+        let total = PI * r + self.length;
+        let (v1, v2) = (PI * r / 2. / total, (PI * r / 2. + self.length) / total);
+        let profile: Vec<_> = arc(r, -h, PI, PI / 2., self.rings, 0., v1)
+            .chain(arc(r, h, PI / 2., 0., self.rings, v2, 1.))
+            .collect();
+
+        let mut mesh = MeshBuilder::new(self.color);
+        mesh.revolve(&profile, self.segments);
+        mesh.build()
+    }
+}
+
+/// A ring shaped tube of radius `minor_radius` whose center line is a circle of radius
+/// `major_radius` around the z axis.
+#[derive(Clone, Copy, Debug)]
+pub struct Torus {
+    major_radius: f32,
+    minor_radius: f32,
+    segments: u32,
+    sides: u32,
+    color: [f32; 3],
+}
+
+impl Torus {
+    pub fn new(major_radius: f32, minor_radius: f32) -> Torus {
+        Torus { major_radius, minor_radius, segments: 48, sides: 24, color: DEFAULT_COLOR }
+    }
+
+    /// The number of steps around the z axis.
+    pub fn segments(mut self, segments: u32) -> Torus {
+        self.segments = segments.max(3);
+        self
+    }
+
+    /// The number of steps around the tube.
+    pub fn sides(mut self, sides: u32) -> Torus {
+        self.sides = sides.max(3);
+        self
+    }
+
+    pub fn color(mut self, color: [f32; 3]) -> Torus {
+        self.color = color;
+        self
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let profile: Vec<_> = (0..=self.sides).map(|k| {
+            let v = k as f32 / self.sides as f32;
+            let (sin, cos) = (2. * PI * v - PI).sin_cos();
+            ProfilePoint::new(self.major_radius + self.minor_radius * cos, self.minor_radius * sin, [cos, sin], v)
+        }).collect();
+
+        let mut mesh = MeshBuilder::new(self.color);
+        mesh.revolve(&profile, self.segments);
+        mesh.build()
+    }
+}
+
+/// A flat circle in the xy plane facing up the z axis.
+#[derive(Clone, Copy, Debug)]
+pub struct Disk {
+    radius: f32,
+    segments: u32,
+    color: [f32; 3],
+}
+
+impl Disk {
+    pub fn new(radius: f32) -> Disk {
+        Disk { radius, segments: 32, color: DEFAULT_COLOR }
+    }
+
+    pub fn segments(mut self, segments: u32) -> Disk {
+        self.segments = segments.max(3);
+        self
+    }
+
+    pub fn color(mut self, color: [f32; 3]) -> Disk {
+        self.color = color;
+        self
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let mut mesh = MeshBuilder::new(self.color);
+        mesh.cap(self.radius, 0., true, self.segments);
+        mesh.build()
+    }
+}
+
+/// A rectangle in the xy plane facing up the z axis, split into a grid of quads.
+#[derive(Clone, Copy, Debug)]
+pub struct Plane {
+    size: [f32; 2],
+    subdivisions: [u32; 2],
+    color: [f32; 3],
+}
+
+impl Plane {
+    pub fn new(width: f32, height: f32) -> Plane {
+        Plane { size: [width, height], subdivisions: [1, 1], color: DEFAULT_COLOR }
+    }
+
+    /// The number of quads along x and along y.
+    pub fn subdivisions(mut self, x: u32, y: u32) -> Plane {
+        self.subdivisions = [x.max(1), y.max(1)];
+        self
+    }
+
+    pub fn color(mut self, color: [f32; 3]) -> Plane {
+        self.color = color;
+        self
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let [nx, ny] = self.subdivisions;
+        let mut mesh = MeshBuilder::new(self.color);
+        for j in 0..=ny {
+            for i in 0..=nx {
+                let (u, v) = (i as f32 / nx as f32, j as f32 / ny as f32);
+                let position = [(u - 0.5) * self.size[0], (v - 0.5) * self.size[1], 0.];
+                mesh.vertex(position, [0., 0., 1.], [u, 1. - v]);
+            }
+        }
+        for j in 0..ny {
+            for i in 0..nx {
+                let a = j * (nx + 1) + i;
+                mesh.quad(a, a + 1, a + nx + 2, a + nx + 1);
+            }
+        }
+        mesh.build()
+    }
+}
+
+/// An arrow from one point to another: a cylindrical shaft with a cone for a head.
+#[derive(Clone, Copy, Debug)]
+pub struct Arrow {
+    from: Vector3<f32>,
+    to: Vector3<f32>,
+    shaft_radius: f32,
+    head_radius: f32,
+    head_length: f32,
+    segments: u32,
+    color: [f32; 3],
+}
+
+impl Arrow {
+    pub fn new(from: [f32; 3], to: [f32; 3]) -> Arrow {
+        Arrow {
+            from: from.into(),
+            to: to.into(),
+            shaft_radius: 0.02,
+            head_radius: 0.05,
+            head_length: 0.15,
+            segments: 16,
+            color: DEFAULT_COLOR,
+        }
+    }
+
+    pub fn shaft_radius(mut self, radius: f32) -> Arrow {
+        self.shaft_radius = radius;
+        self
+    }
+
+    /// The radius and length of the cone at the tip. The head is shortened to fit
+    /// arrows shorter than it.
+    pub fn head(mut self, radius: f32, length: f32) -> Arrow {
+        self.head_radius = radius;
+        self.head_length = length;
+        self
+    }
+
+    pub fn segments(mut self, segments: u32) -> Arrow {
+        self.segments = segments.max(3);
+        self
+    }
+
+    pub fn color(mut self, color: [f32; 3]) -> Arrow {
+        self.color = color;
+        self
+    }
+
+    pub fn tessellate(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let direction = self.to - self.from;
+        let length = direction.magnitude();
+        if length == 0. {
+            return (Vec::new(), Vec::new());
+        }
+        let neck = length - self.head_length.min(length);
+        let (shaft, head) = (self.shaft_radius, self.head_radius);
+
+        // Build the arrow along z, then turn it to point the right way.
+        let mut mesh = MeshBuilder::new(self.color);
+        mesh.cap(shaft, 0., false, self.segments);
+        mesh.revolve(&[ProfilePoint::new(shaft, 0., [1., 0.], 0.), ProfilePoint::new(shaft, neck, [1., 0.], 1.)], self.segments);
+        mesh.revolve(&[ProfilePoint::new(shaft, neck, [0., -1.], 0.), ProfilePoint::new(head, neck, [0., -1.], 1.)], self.segments);
+        cone(&mut mesh, head, neck, length, self.segments);
+
+        let rotation = Quaternion::from_arc(Vector3::unit_z(), direction / length, Some(Vector3::unit_x()));
+        let (mut vertices, indices) = mesh.build();
+        for vertex in vertices.iter_mut() {
+            vertex.position = (rotation.rotate_vector(vertex.position.into()) + self.from).into();
+            vertex.normal = rotation.rotate_vector(vertex.normal.into()).into();
+        }
+        (vertices, indices)
+    }
+}
+
+macro_rules! impl_draw {
+    ($($shape:ty),*) => {
+        $(
+            impl Draw for $shape {
+                fn draw(&self, app: &App) -> Geometry {
+                    let (vertices, indices) = self.tessellate();
+                    Geometry::new_mesh(app, &vertices, &indices)
+                }
+
+                fn mesh(&self) -> Option<Mesh> {
+                    let (vertices, indices) = self.tessellate();
+                    Some(Mesh::from_vertices(&vertices, &indices))
+                }
+            }
+        )*
+    };
+}
+
+impl_draw!(UvSphere, Icosphere, Cuboid, Cylinder, Cone, Capsule, Torus, Disk, Plane, Arrow);
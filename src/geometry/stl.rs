@@ -0,0 +1,233 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+use cgmath::{InnerSpace, Vector3};
+
+use super::mesh::DEFAULT_COLOR;
+use super::{Draw, Mesh, MeshError};
+use crate::vertex::Vertex;
+
+/// The two encodings of an STL file. Binary files are about a fifth of the size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StlFormat {
+    Ascii,
+    Binary,
+}
+
+/// A problem reading or writing an STL file.
+#[derive(Debug)]
+pub enum StlError {
+    Io(std::io::Error),
+    /// A line of an ASCII file that couldn't be understood, counting from 1.
+    Parse { line: usize, message: String },
+    /// A binary file shorter than the number of triangles in its header needs.
+    Truncated { triangles: u32, len: usize },
+    /// The object written has no triangles, like a line.
+    NoTriangles,
+    /// The object written doesn't enclose a volume, so it can't be printed.
+    NotClosed(Vec<MeshError>),
+    /// The mesh written has triangles that refer to missing vertices or aren't whole.
+    InvalidMesh(Vec<MeshError>),
+}
+
+impl fmt::Display for StlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StlError::Io(error) => write!(f, "{}", error),
+            StlError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            StlError::Truncated { triangles, len } =>
+                write!(f, "{} bytes are too few for the {} triangles of a binary STL file", len, triangles),
+            StlError::NoTriangles => write!(f, "only triangle meshes can be written as STL"),
+            StlError::NotClosed(errors) => write_errors(f, "mesh isn't closed", errors),
+            StlError::InvalidMesh(errors) => write_errors(f, "mesh is invalid", errors),
+        }
+    }
+}
+
+fn write_errors(f: &mut fmt::Formatter<'_>, problem: &str, errors: &[MeshError]) -> fmt::Result {
+    write!(f, "{}", problem)?;
+    for (i, error) in errors.iter().enumerate() {
+        write!(f, "{} {}", if i == 0 { ":" } else { "," }, error)?;
+    }
+    Ok(())
+}
+
+impl std::error::Error for StlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StlError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for StlError {
+    fn from(error: std::io::Error) -> StlError {
+        StlError::Io(error)
+    }
+}
+
+const HEADER_LEN: usize = 80;
+const TRIANGLE_LEN: usize = 50;
+
+/// Reads an STL file in either format.
+///
+/// Every triangle gets its own three vertices, with the triangle's normal for flat
+/// shading; [`weld`](Mesh::weld) the mesh to share them. Normals follow the winding of
+/// the triangles rather than the normals written in the file, which are often wrong.
+pub fn read(mut reader: impl Read) -> Result<Mesh, StlError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    // Binary files may start with "solid" too, but only they have the length their header gives.
+    if bytes.len() >= HEADER_LEN + 4 {
+        let triangles = u32::from_le_bytes(bytes[HEADER_LEN..HEADER_LEN + 4].try_into().unwrap());
+        if bytes.len() == HEADER_LEN + 4 + triangles as usize * TRIANGLE_LEN {
+            return Ok(read_binary(&bytes));
+        }
+    }
+    match std::str::from_utf8(&bytes) {
+        Ok(text) if text.trim_start().starts_with("solid") => read_ascii(text),
+        _ if bytes.len() >= HEADER_LEN + 4 => Err(StlError::Truncated {
+            triangles: u32::from_le_bytes(bytes[HEADER_LEN..HEADER_LEN + 4].try_into().unwrap()),
+            len: bytes.len(),
+        }),
+        _ => Err(StlError::Parse { line: 1, message: "not an STL file".to_string() }),
+    }
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<Mesh, StlError> {
+    read(File::open(path)?)
+}
+
+fn read_binary(bytes: &[u8]) -> Mesh {
+    let triangles = bytes[HEADER_LEN + 4..].chunks_exact(TRIANGLE_LEN).map(|chunk| {
+        let float = |at: usize| f32::from_le_bytes(chunk[at..at + 4].try_into().unwrap());
+        // The normal comes first, then the corners; the attribute bytes at the end are unused.
+        [1, 2, 3].map(|k| [0, 1, 2].map(|j| float(12 * k + 4 * j)))
+    });
+    mesh_from_triangles(triangles)
+}
+
+fn read_ascii(text: &str) -> Result<Mesh, StlError> {
+    let mut triangles = Vec::new();
+    let mut corners = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let error = |message: String| StlError::Parse { line: i + 1, message };
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("facet") => corners.clear(),
+            Some("vertex") => {
+                let values = words
+                    .map(|x| x.parse::<f32>().map_err(|_| error(format!("{:?} isn't a number", x))))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if values.len() != 3 {
+                    return Err(error(format!("a vertex needs 3 coordinates, found {}", values.len())));
+                }
+                corners.push([values[0], values[1], values[2]]);
+            }
+            Some("endfacet") => {
+                let corners: [[f32; 3]; 3] = corners.as_slice().try_into()
+                    .map_err(|_| error(format!("a facet needs 3 vertices, found {}", corners.len())))?;
+                triangles.push(corners);
+            }
+            // The facet normal is recomputed, and the rest only marks out the facets.
+            Some("solid" | "outer" | "endloop" | "endsolid") | None => {}
+            Some(word) => return Err(error(format!("unexpected {:?}", word))),
+        }
+    }
+    Ok(mesh_from_triangles(triangles))
+}
+
+fn mesh_from_triangles(triangles: impl IntoIterator<Item = [[f32; 3]; 3]>) -> Mesh {
+    let vertices: Vec<Vertex> = triangles.into_iter().flat_map(|corners| {
+        let normal = facet_normal(corners);
+        corners.map(|p| Vertex::with_normal(p, DEFAULT_COLOR, normal))
+    }).collect();
+    let indices: Vec<u32> = (0..vertices.len() as u32).collect();
+    Mesh::from_vertices(&vertices, &indices)
+}
+
+/// The unit normal a triangle faces by its winding, or zero if it has no area.
+fn facet_normal(corners: [[f32; 3]; 3]) -> [f32; 3] {
+    let [a, b, c] = corners.map(Vector3::from);
+    let normal = (b - a).cross(c - a);
+    if normal.magnitude2() > 0. { normal.normalize().into() } else { [0.; 3] }
+}
+
+/// Writes the triangles `object` tessellates into as STL, for 3D printing.
+///
+/// Vertices closer than a millionth of the mesh's size are welded first, which joins
+/// the faces that primitives and surfaces give their own vertices. The result must then
+/// be [closed](Mesh::validate_closed), or nothing is written.
+///
+/// ```no_run
+/// use draw3d::geometry::primitives::Torus;
+/// use draw3d::geometry::stl::{self, StlFormat};
+///
+/// stl::save("torus.stl", &Torus::new(1., 0.3), StlFormat::Binary).unwrap();
+/// ```
+pub fn write(writer: impl Write, object: &dyn Draw, format: StlFormat) -> Result<(), StlError> {
+    let mut mesh = object.mesh().ok_or(StlError::NoTriangles)?;
+    let size = mesh.bounding_box().map_or(0., |b| b.size().into_iter().fold(0., f32::max));
+    // A mesh without any size still welds the vertices that coincide.
+    mesh.weld((size * 1e-6).max(f32::MIN_POSITIVE));
+    mesh.validate_closed().map_err(StlError::NotClosed)?;
+    write_mesh(writer, &mesh, format)
+}
+
+pub fn save(path: impl AsRef<Path>, object: &dyn Draw, format: StlFormat) -> Result<(), StlError> {
+    write(BufWriter::new(File::create(path)?), object, format)
+}
+
+/// Writes the triangles of `mesh` as they are, closed or not, as long as their indices
+/// are valid.
+pub fn write_mesh(mut writer: impl Write, mesh: &Mesh, format: StlFormat) -> Result<(), StlError> {
+    if mesh.indices.len() < 3 {
+        return Err(StlError::NoTriangles);
+    }
+    if let Err(errors) = mesh.validate() {
+        // Other problems, like degenerate triangles, still make a file.
+        let errors: Vec<MeshError> = errors.into_iter()
+            .filter(|e| matches!(e, MeshError::IncompleteTriangle { .. } | MeshError::IndexOutOfRange { .. }))
+            .collect();
+        if !errors.is_empty() {
+            return Err(StlError::InvalidMesh(errors));
+        }
+    }
+    let triangles = mesh.triangles().map(|t| t.map(|i| mesh.positions[i as usize]));
+    match format {
+        StlFormat::Ascii => {
+            writeln!(writer, "solid draw3d")?;
+            for corners in triangles {
+                let [x, y, z] = facet_normal(corners);
+                writeln!(writer, "  facet normal {:e} {:e} {:e}", x, y, z)?;
+                writeln!(writer, "    outer loop")?;
+                for [x, y, z] in corners {
+                    writeln!(writer, "      vertex {:e} {:e} {:e}", x, y, z)?;
+                }
+                writeln!(writer, "    endloop")?;
+                writeln!(writer, "  endfacet")?;
+            }
+            writeln!(writer, "endsolid draw3d")?;
+        }
+        StlFormat::Binary => {
+            let mut header = [b' '; HEADER_LEN];
+            // Not "solid", which would make some readers take the file for ASCII.
+            let title = b"Binary STL written by draw3d";
+            header[..title.len()].copy_from_slice(title);
+            writer.write_all(&header)?;
+            writer.write_all(&(mesh.triangle_count() as u32).to_le_bytes())?;
+            for corners in triangles {
+                for value in std::iter::once(facet_normal(corners)).chain(corners).flatten() {
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+                writer.write_all(&[0; 2])?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
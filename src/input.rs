@@ -0,0 +1,90 @@
+use std::collections::HashSet;
+
+use winit::{
+    event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+/// Keyboard and mouse state accumulated from the window's events.
+#[derive(Clone, Debug, Default)]
+pub struct InputState {
+    keys: HashSet<KeyCode>,
+    mouse_buttons: HashSet<MouseButton>,
+    mouse_position: Option<[f32; 2]>,
+    scroll_delta: [f32; 2],
+}
+
+impl InputState {
+    pub fn new() -> InputState {
+        InputState::default()
+    }
+
+    pub fn process_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                if let PhysicalKey::Code(code) = event.physical_key {
+                    match event.state {
+                        ElementState::Pressed => self.keys.insert(code),
+                        ElementState::Released => self.keys.remove(&code),
+                    };
+                }
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                match state {
+                    ElementState::Pressed => self.mouse_buttons.insert(*button),
+                    ElementState::Released => self.mouse_buttons.remove(button),
+                };
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.mouse_position = Some([position.x as f32, position.y as f32]);
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.mouse_position = None;
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let [x, y] = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => [*x, *y],
+                    // Treat 50 pixels as one line so both kinds of wheel scroll at the same speed.
+                    MouseScrollDelta::PixelDelta(position) => [position.x as f32 / 50., position.y as f32 / 50.],
+                };
+                self.scroll_delta[0] += x;
+                self.scroll_delta[1] += y;
+            }
+            // Releases that happen while the window is unfocused never reach us.
+            WindowEvent::Focused(false) => {
+                self.keys.clear();
+                self.mouse_buttons.clear();
+            }
+            _ => {}
+        }
+    }
+
+    /// Clears the per-frame scroll delta once a frame has been updated and drawn. The app
+    /// calls this itself after every frame.
+    pub fn end_frame(&mut self) {
+        self.scroll_delta = [0., 0.];
+    }
+
+    pub fn key_pressed(&self, key: KeyCode) -> bool {
+        self.keys.contains(&key)
+    }
+
+    pub fn pressed_keys(&self) -> impl Iterator<Item = &KeyCode> {
+        self.keys.iter()
+    }
+
+    pub fn mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.contains(&button)
+    }
+
+    /// The cursor position in physical pixels from the top-left of the window,
+    /// or `None` if the cursor is outside the window.
+    pub fn mouse_position(&self) -> Option<[f32; 2]> {
+        self.mouse_position
+    }
+
+    /// Lines scrolled horizontally and vertically since the last frame.
+    pub fn scroll_delta(&self) -> [f32; 2] {
+        self.scroll_delta
+    }
+}
@@ -0,0 +1,95 @@
+use cgmath::Vector3;
+
+use crate::transform::Transform;
+
+/// One copy of an instanced geometry, placed by `transform`.
+///
+/// When `color` is set it replaces the vertex colors of this copy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Instance {
+    pub transform: Transform,
+    pub color: Option<[f32; 3]>,
+}
+
+impl Instance {
+    pub fn new(transform: Transform) -> Instance {
+        Instance { transform, color: None }
+    }
+
+    /// An instance translated to `position`.
+    pub fn at(position: impl Into<Vector3<f32>>) -> Instance {
+        let mut transform = Transform::new();
+        transform.translate(position);
+        Instance::new(transform)
+    }
+
+    pub fn color(mut self, color: [f32; 3]) -> Instance {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn to_raw(&self) -> InstanceRaw {
+        let color = match self.color {
+            Some([r, g, b]) => [r, g, b, 1.],
+            None => [1., 1., 1., 0.],
+        };
+        InstanceRaw {
+            model: self.transform.matrix().into(),
+            color,
+        }
+    }
+}
+
+impl Default for Instance {
+    fn default() -> Instance {
+        Instance::new(Transform::new())
+    }
+}
+
+/// Per-instance vertex data. The alpha channel of `color` blends between
+/// the vertex color (0) and the instance color (1).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct InstanceRaw {
+    model: [[f32; 4]; 4],
+    color: [f32; 4],
+}
+
+impl InstanceRaw {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        use std::mem;
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            // Switch from one instance to the next only after a whole copy of the geometry is drawn.
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                // A mat4 takes up four vertex slots, one per column.
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                    shader_location: 8,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 16]>() as wgpu::BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
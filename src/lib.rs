@@ -1,12 +1,26 @@
 // #![allow(dead_code)]
 
 pub mod app;
+pub mod camera;
+pub mod capture;
+pub mod colormap;
+pub mod controller;
 pub mod draw;
 pub mod vertex;
 pub mod geometry;
+pub mod input;
+pub mod instance;
+pub mod light;
+pub mod scene;
+pub mod texture;
+pub mod time;
+pub mod transform;
 
 use app::AppBuilder;
 
+pub use cgmath;
+pub use winit;
+
 #[cfg(target_arch="wasm32")] 
 use wasm_bindgen::prelude::wasm_bindgen;
 
@@ -26,14 +40,15 @@ pub fn app<M>(model: app::ModelFn<M>) -> app::AppBuilder<M> where M: 'static{
 // #[cfg_attr(target_arch="wasm32", wasm_bindgen(start))]
 pub async fn run_example() {
     use app::App;
-    use geometry::ParametricSurface;
+    use geometry::{Domain, ParametricSurface};
+    use scene::Scene;
     use std::f32::consts::PI;
 
     struct Model{
-        surface: ParametricSurface,
+        scene: Scene,
     }
 
-    fn model(_app: &App) -> Model{
+    fn model(app: &App) -> Model{
         // let vertices= vec![
         // Vertex { position: [-0.0868241, 0.49240386, 0.0], color: [0.5, 0.0, 0.5] }, // A
         // Vertex { position: [-0.49513406, 0.06958647, 0.0], color: [0.5, 0.0, 0.5] }, // B
@@ -42,16 +57,16 @@ pub async fn run_example() {
         // Vertex { position: [0.44147372, 0.2347359, 0.0], color: [0.5, 0.0, 0.5] }, // E
         // ];
 
-        fn sphere(theta: f32, phi: f32) -> [f32; 3] {
-            let r = 0.5;
+        let r = 0.5;
+        let sphere = move |phi: f32, theta: f32| {
             let x = r*theta.cos()*phi.sin();
             let y = r*theta.sin()*phi.sin();
             let z = r*phi.cos();
             [x, y, z]
-        }
+        };
 
-        let r1 = (0..32).map(|n| 2.*PI*n as f32/20.).collect();
-        let r2 = (0..16).map(|n| PI*n as f32/10.-PI/2.).collect();
+        let r1 = Domain::linspace(0., PI, 17).degenerate();
+        let r2 = Domain::linspace(0., 2.*PI, 32).periodic();
 
         let sphere = ParametricSurface::new(sphere, r1, r2);
 
@@ -63,27 +78,30 @@ pub async fn run_example() {
         // let circle = Polygon::new(vertices);
 
         // Model { surface: circle }
-        return Model {surface: sphere};
+        // Tessellated and uploaded once, rather than every frame.
+        let mut scene = Scene::new();
+        scene.add(&sphere, app);
+        Model {scene}
     }
 
     fn view(app: &mut App, model: &Model){
         let mut draw = app.draw();
         draw.update_background_color((0.1, 0.2, 0.3));
-        draw.add(&model.surface, app);
+        draw.add_scene(&model.scene);
 
         app.draw_to_frame(draw)
     }
     
 
     #[cfg(not(target_arch="wasm32"))]
-    let _run_example = pollster::block_on(app(model)
+    pollster::block_on(app(model)
         .view(view)
         .run());
 
 
     #[cfg(target_arch="wasm32")]
     {
-    let _run_example = app(model)
+    app(model)
         .view(view)
         .run().await;
     }
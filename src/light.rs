@@ -0,0 +1,97 @@
+/// The most lights a single frame can be lit by; extra lights are ignored.
+pub const MAX_LIGHTS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Light {
+    /// Light arriving from infinitely far away, travelling along `direction`.
+    Directional { direction: [f32; 3], color: [f32; 3] },
+    /// Light emitted in all directions from `position`.
+    Point { position: [f32; 3], color: [f32; 3] },
+}
+
+impl Light {
+    pub fn directional(direction: [f32; 3], color: [f32; 3]) -> Light {
+        Light::Directional { direction, color }
+    }
+
+    pub fn point(position: [f32; 3], color: [f32; 3]) -> Light {
+        Light::Point { position, color }
+    }
+
+    fn to_raw(self) -> LightRaw {
+        match self {
+            // The shader works with the direction towards the light.
+            Light::Directional { direction: [x, y, z], color: [r, g, b] } => LightRaw {
+                position: [-x, -y, -z, 0.],
+                color: [r, g, b, 1.],
+            },
+            Light::Point { position: [x, y, z], color: [r, g, b] } => LightRaw {
+                position: [x, y, z, 1.],
+                color: [r, g, b, 1.],
+            },
+        }
+    }
+}
+
+/// Ambient, directional and point lights used to shade geometry with normals.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lighting {
+    pub ambient: [f32; 3],
+    pub lights: Vec<Light>,
+    /// Strength of the Blinn-Phong highlight, 0 for purely diffuse shading.
+    pub specular: f32,
+    pub shininess: f32,
+}
+
+impl Lighting {
+    /// A dim ambient term plus a white light shining over the default camera's shoulder.
+    pub fn new() -> Lighting {
+        Lighting {
+            ambient: [0.2, 0.2, 0.2],
+            lights: vec![Light::directional([0.3, 1., -1.], [0.8, 0.8, 0.8])],
+            specular: 0.3,
+            shininess: 32.,
+        }
+    }
+
+    pub(crate) fn to_uniform(&self) -> LightUniform {
+        let mut lights = [LightRaw::default(); MAX_LIGHTS];
+        for (raw, light) in lights.iter_mut().zip(self.lights.iter()) {
+            *raw = light.to_raw();
+        }
+        let [r, g, b] = self.ambient;
+        LightUniform {
+            ambient: [r, g, b, 1.],
+            specular: self.specular,
+            shininess: self.shininess,
+            count: self.lights.len().min(MAX_LIGHTS) as u32,
+            _padding: 0,
+            lights,
+        }
+    }
+}
+
+impl Default for Lighting {
+    fn default() -> Lighting {
+        Lighting::new()
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightRaw {
+    // w is 0 for directional lights and 1 for point lights.
+    position: [f32; 4],
+    color: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct LightUniform {
+    ambient: [f32; 4],
+    specular: f32,
+    shininess: f32,
+    count: u32,
+    _padding: u32,
+    lights: [LightRaw; MAX_LIGHTS],
+}
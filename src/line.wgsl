@@ -0,0 +1,239 @@
+// Wide lines: every segment is an instance of a quad covering the segment and its cap or
+// join in screen space, and the fragment shader cuts the exact shape out of it with
+// signed distances in pixels, blending the outermost pixel for anti-aliasing.
+
+struct CameraUniform {
+    view_position: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    // The size of the render target in pixels.
+    viewport: vec2<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct ModelUniform {
+    model: mat4x4<f32>,
+    normal: mat4x4<f32>,
+    screen_space: u32,
+};
+@group(1) @binding(0)
+var<uniform> model_uniform: ModelUniform;
+
+const CAP_BUTT: u32 = 0u;
+const CAP_SQUARE: u32 = 1u;
+const CAP_ROUND: u32 = 2u;
+
+const JOIN_MITER: u32 = 0u;
+const JOIN_BEVEL: u32 = 1u;
+const JOIN_ROUND: u32 = 2u;
+
+struct SegmentInput {
+    @location(0) start: vec3<f32>,
+    @location(1) end: vec3<f32>,
+    @location(2) next: vec3<f32>,
+    @location(3) start_color: vec3<f32>,
+    @location(4) end_color: vec3<f32>,
+    // Distance along the line to start and to end, width and miter limit.
+    @location(5) shape: vec4<f32>,
+    @location(6) dash: vec2<f32>,
+    // Cap, join, and whether the start and the end are joined to another segment.
+    @location(7) style: vec4<u32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // The fragment's position in pixels along the segment from its start and across it.
+    // Corners have a w of 1, so this interpolates linearly on screen.
+    @location(0) local: vec2<f32>,
+    // The segment's length and half width in pixels.
+    @location(1) @interpolate(flat) size: vec2<f32>,
+    // The direction of the next segment, along and across this one.
+    @location(2) @interpolate(flat) next_direction: vec2<f32>,
+    @location(3) @interpolate(flat) start_color: vec3<f32>,
+    @location(4) @interpolate(flat) end_color: vec3<f32>,
+    @location(5) @interpolate(flat) distances: vec2<f32>,
+    @location(6) @interpolate(flat) dash: vec2<f32>,
+    @location(7) @interpolate(flat) miter_limit: f32,
+    @location(8) @interpolate(flat) style: vec4<u32>,
+};
+
+fn to_clip(position: vec3<f32>) -> vec4<f32> {
+    let world_position = model_uniform.model * vec4<f32>(position, 1.0);
+    if (model_uniform.screen_space != 0u) {
+        return world_position;
+    }
+    return camera.view_proj * world_position;
+}
+
+fn to_pixels(clip: vec4<f32>) -> vec2<f32> {
+    return (clip.xy / clip.w * 0.5 + 0.5) * camera.viewport;
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) corner: u32, segment: SegmentInput) -> VertexOutput {
+    var out: VertexOutput;
+    var start = to_clip(segment.start);
+    var end = to_clip(segment.end);
+    var distances = segment.shape.xy;
+    var start_color = segment.start_color;
+    var end_color = segment.end_color;
+    var style = segment.style;
+
+    // Cut the segment off at the near plane. A cut end has no join, since the
+    // neighbouring segment is cut there too.
+    if (start.z < 0.0 && end.z < 0.0) {
+        out.clip_position = vec4<f32>(2.0, 2.0, 2.0, 1.0);
+        return out;
+    }
+    if (start.z < 0.0) {
+        let t = start.z / (start.z - end.z);
+        start = mix(start, end, t);
+        distances.x = mix(distances.x, distances.y, t);
+        start_color = mix(start_color, end_color, t);
+        style.z = 0u;
+    } else if (end.z < 0.0) {
+        let t = start.z / (start.z - end.z);
+        end = mix(start, end, t);
+        distances.y = mix(distances.x, distances.y, t);
+        end_color = mix(start_color, end_color, t);
+        style.w = 0u;
+    }
+
+    let a = to_pixels(start);
+    let b = to_pixels(end);
+    let len = distance(a, b);
+    var direction = vec2<f32>(1.0, 0.0);
+    if (len > 1e-4) {
+        direction = (b - a) / len;
+    }
+    let normal = vec2<f32>(-direction.y, direction.x);
+
+    var next_direction = vec2<f32>(1.0, 0.0);
+    if (style.w != 0u) {
+        let next = to_clip(segment.next);
+        let to_next = to_pixels(next) - b;
+        if (next.z < 0.0 || dot(to_next, to_next) < 1e-8) {
+            style.w = 0u;
+        } else {
+            let d = normalize(to_next);
+            next_direction = vec2<f32>(dot(d, direction), dot(d, normal));
+        }
+    }
+
+    // Leave a pixel around the shape for anti-aliasing. A miter reaches at most
+    // `miter_limit` half widths past the end, but never further than half a width across.
+    let half_width = segment.shape.z * 0.5;
+    let side = half_width + 1.0;
+    var ahead = side;
+    if (style.w != 0u && style.y == JOIN_MITER) {
+        ahead = half_width * max(segment.shape.w, 1.0) + 1.0;
+    }
+
+    // Corners 0 and 3 are at the start, 1 and 2 at the end; 0 and 1 on the right.
+    let at_end = corner == 1u || corner == 2u;
+    let across = select(-side, side, corner >= 2u);
+    let along = select(-side, len + ahead, at_end);
+    let pixel = a + direction * along + normal * across;
+    let depth = select(start.z / start.w, end.z / end.w, at_end);
+
+    out.clip_position = vec4<f32>(pixel / camera.viewport * 2.0 - 1.0, depth, 1.0);
+    out.local = vec2<f32>(along, across);
+    out.size = vec2<f32>(len, half_width);
+    out.next_direction = next_direction;
+    out.start_color = start_color;
+    out.end_color = end_color;
+    out.distances = distances;
+    out.dash = segment.dash;
+    out.miter_limit = segment.shape.w;
+    out.style = style;
+    return out;
+}
+
+// Signed distance to a cap, with `q` relative to the end point and pointing away from the line.
+fn cap_distance(cap: u32, q: vec2<f32>, half_width: f32) -> f32 {
+    switch cap {
+        case CAP_SQUARE: {
+            return max(abs(q.y) - half_width, q.x - half_width);
+        }
+        case CAP_ROUND: {
+            return length(q) - half_width;
+        }
+        default: {
+            return max(abs(q.y) - half_width, q.x);
+        }
+    }
+}
+
+// Signed distance to the outside of a join, with `q` relative to the shared point.
+fn join_distance(join: u32, q: vec2<f32>, next: vec2<f32>, miter_limit: f32, half_width: f32) -> f32 {
+    if (join == JOIN_ROUND) {
+        return length(q) - half_width;
+    }
+    // The normals of both segments on the outside of the turn.
+    let outside = select(1.0, -1.0, next.y > 0.0);
+    let normal = vec2<f32>(0.0, outside);
+    let next_normal = vec2<f32>(-next.y, next.x) * outside;
+    var bisector = vec2<f32>(1.0, 0.0);
+    if (dot(normal + next_normal, normal + next_normal) > 1e-8) {
+        bisector = normalize(normal + next_normal);
+    }
+    // The miter reaches 1 / cos(half the turn) half widths past the shared point.
+    let cos_half_turn = dot(normal, bisector);
+    let bevel = dot(q, bisector) - half_width * cos_half_turn;
+    if (join == JOIN_MITER && cos_half_turn * miter_limit >= 1.0) {
+        return max(max(dot(q, normal), dot(q, next_normal)) - half_width, abs(q.y) - half_width);
+    }
+    return max(bevel, abs(q.y) - half_width);
+}
+
+// Signed distance to the nearest dash, in the line's own units.
+fn dash_distance(along: f32, dash: vec2<f32>) -> f32 {
+    let period = dash.x + dash.y;
+    let phase = along - floor(along / period) * period;
+    if (phase < dash.x) {
+        return -min(phase, dash.x - phase);
+    }
+    return min(phase - dash.x, period - phase);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let s = in.local.x;
+    let t = in.local.y;
+    let len = in.size.x;
+    let half_width = in.size.y;
+
+    var d = abs(t) - half_width;
+    if (s < 0.0) {
+        // The previous segment draws the join.
+        if (in.style.z != 0u) {
+            discard;
+        }
+        d = cap_distance(in.style.x, vec2<f32>(-s, t), half_width);
+    } else if (s > len) {
+        let q = vec2<f32>(s - len, t);
+        if (in.style.w != 0u) {
+            // The next segment draws everything ahead of its start.
+            if (dot(q, in.next_direction) >= 0.0) {
+                discard;
+            }
+            d = join_distance(in.style.y, q, in.next_direction, in.miter_limit, half_width);
+        } else {
+            d = cap_distance(in.style.x, q, half_width);
+        }
+    }
+
+    let along = clamp(s / max(len, 1e-4), 0.0, 1.0);
+    let span = in.distances.y - in.distances.x;
+    if (in.dash.x > 0.0 && span > 0.0) {
+        // Pixels per unit along the segment.
+        let scale = len / span;
+        d = max(d, dash_distance(mix(in.distances.x, in.distances.y, along), in.dash) * scale);
+    }
+
+    let alpha = clamp(0.5 - d, 0.0, 1.0);
+    if (alpha <= 0.0) {
+        discard;
+    }
+    return vec4<f32>(mix(in.start_color, in.end_color, along), alpha);
+}
@@ -0,0 +1,318 @@
+use std::cell::Cell;
+
+use cgmath::{Matrix4, SquareMatrix};
+
+use crate::app::App;
+use crate::geometry::{Draw, Geometry};
+use crate::transform::Transform;
+
+/// A handle to a node of a [`Scene`]. Handles to removed nodes are never reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: u32,
+    generation: u32,
+}
+
+struct Node {
+    geometry: Option<Geometry>,
+    transform: Transform,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    visible: bool,
+    /// The parent's world matrix times the node's transform, valid unless `dirty`.
+    world: Cell<Matrix4<f32>>,
+    /// Set when the node or one of its ancestors moves. A dirty node's descendants are
+    /// all dirty too.
+    dirty: Cell<bool>,
+}
+
+struct Slot {
+    generation: u32,
+    node: Option<Node>,
+}
+
+/// Geometry kept on the GPU across frames, arranged in a tree of nodes whose transforms
+/// apply to everything below them.
+///
+/// Nodes are uploaded once, when they're added or given new geometry. Drawing the scene
+/// with [`DrawState::add_scene`](crate::draw::DrawState::add_scene) only shares their
+/// buffers, and world matrices are recomputed only below nodes that moved.
+///
+/// This means an [animated](Draw::is_animated) surface stays as it was when it was
+/// added. Keep such a surface in the model and [`update`](Scene::update) its node every
+/// frame, or draw it with [`DrawState::add`](crate::draw::DrawState::add) instead.
+///
+/// ```no_run
+/// use draw3d::app::App;
+/// use draw3d::cgmath::{Quaternion, Rad, Rotation3};
+/// use draw3d::geometry::primitives::{Cuboid, UvSphere};
+/// use draw3d::scene::{NodeId, Scene};
+///
+/// struct Model {
+///     scene: Scene,
+///     arm: NodeId,
+/// }
+///
+/// fn model(app: &App) -> Model {
+///     let mut scene = Scene::new();
+///     let arm = scene.add(&Cuboid::new([1., 0.1, 0.1]), app);
+///     let hand = scene.add_child(arm, &UvSphere::new(0.2), app);
+///     scene.transform_mut(hand).translate((0.5, 0., 0.));
+///     Model { scene, arm }
+/// }
+///
+/// fn update(_app: &App, model: &mut Model) {
+///     // Only the arm and the hand below it are moved; nothing is uploaded again.
+///     model.scene.transform_mut(model.arm).rotate(Quaternion::from_angle_z(Rad(0.01)));
+/// }
+///
+/// fn view(app: &mut App, model: &Model) {
+///     let mut draw = app.draw();
+///     draw.add_scene(&model.scene);
+///     app.draw_to_frame(draw);
+/// }
+/// ```
+#[derive(Default)]
+pub struct Scene {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    roots: Vec<NodeId>,
+    len: usize,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene::default()
+    }
+
+    /// Uploads `object` and adds it as a new top level node.
+    pub fn add(&mut self, object: &dyn Draw, app: &App) -> NodeId {
+        self.insert(Some(upload(object, app)), None)
+    }
+
+    pub fn add_geometry(&mut self, geometry: Geometry) -> NodeId {
+        self.insert(Some(geometry), None)
+    }
+
+    /// Adds a top level node without geometry, for grouping other nodes under one transform.
+    pub fn add_group(&mut self) -> NodeId {
+        self.insert(None, None)
+    }
+
+    /// Uploads `object` and adds it below `parent`.
+    pub fn add_child(&mut self, parent: NodeId, object: &dyn Draw, app: &App) -> NodeId {
+        self.insert(Some(upload(object, app)), Some(parent))
+    }
+
+    /// Adds a node without geometry below `parent`, for grouping nodes.
+    pub fn add_child_group(&mut self, parent: NodeId) -> NodeId {
+        self.insert(None, Some(parent))
+    }
+
+    fn insert(&mut self, geometry: Option<Geometry>, parent: Option<NodeId>) -> NodeId {
+        if let Some(parent) = parent {
+            // Check before the new node takes a slot.
+            self.node(parent);
+        }
+        let node = Node {
+            geometry,
+            transform: Transform::new(),
+            parent,
+            children: Vec::new(),
+            visible: true,
+            world: Cell::new(Matrix4::identity()),
+            dirty: Cell::new(true),
+        };
+        let id = match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.node = Some(node);
+                NodeId { index, generation: slot.generation }
+            }
+            None => {
+                self.slots.push(Slot { generation: 0, node: Some(node) });
+                NodeId { index: self.slots.len() as u32 - 1, generation: 0 }
+            }
+        };
+        match parent {
+            Some(parent) => self.node_mut(parent).children.push(id),
+            None => self.roots.push(id),
+        }
+        self.len += 1;
+        id
+    }
+
+    /// Removes `node` and everything below it. Returns false if it was already removed.
+    pub fn remove(&mut self, node: NodeId) -> bool {
+        if !self.contains(node) {
+            return false;
+        }
+        match self.node(node).parent {
+            Some(parent) => self.node_mut(parent).children.retain(|&c| c != node),
+            None => self.roots.retain(|&r| r != node),
+        }
+        let mut stack = vec![node];
+        while let Some(id) = stack.pop() {
+            let slot = &mut self.slots[id.index as usize];
+            let removed = slot.node.take().unwrap();
+            slot.generation += 1;
+            self.free.push(id.index);
+            self.len -= 1;
+            stack.extend(removed.children);
+        }
+        true
+    }
+
+    pub fn contains(&self, node: NodeId) -> bool {
+        self.slots.get(node.index as usize)
+            .is_some_and(|slot| slot.generation == node.generation && slot.node.is_some())
+    }
+
+    /// The number of nodes in the scene.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The nodes without a parent, in the order they're drawn.
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    pub fn parent(&self, node: NodeId) -> Option<NodeId> {
+        self.node(node).parent
+    }
+
+    pub fn children(&self, node: NodeId) -> &[NodeId] {
+        &self.node(node).children
+    }
+
+    /// Moves `node` below `parent`, or to the top level for `None`, keeping its own transform.
+    ///
+    /// Panics if `parent` is `node` or below it.
+    pub fn set_parent(&mut self, node: NodeId, parent: Option<NodeId>) {
+        let mut ancestor = parent;
+        while let Some(id) = ancestor {
+            assert!(id != node, "a node can't be moved below itself");
+            ancestor = self.node(id).parent;
+        }
+
+        match self.node(node).parent {
+            Some(old) => self.node_mut(old).children.retain(|&c| c != node),
+            None => self.roots.retain(|&r| r != node),
+        }
+        match parent {
+            Some(new) => self.node_mut(new).children.push(node),
+            None => self.roots.push(node),
+        }
+        self.node_mut(node).parent = parent;
+        self.mark_dirty(node);
+    }
+
+    pub fn transform(&self, node: NodeId) -> &Transform {
+        &self.node(node).transform
+    }
+
+    pub fn set_transform(&mut self, node: NodeId, transform: Transform) {
+        self.node_mut(node).transform = transform;
+        self.mark_dirty(node);
+    }
+
+    /// The transform of `node` relative to its parent, for changing in place.
+    pub fn transform_mut(&mut self, node: NodeId) -> &mut Transform {
+        self.mark_dirty(node);
+        &mut self.node_mut(node).transform
+    }
+
+    /// The transforms of `node` and all its ancestors combined.
+    pub fn world_matrix(&self, node: NodeId) -> Matrix4<f32> {
+        let n = self.node(node);
+        if n.dirty.get() {
+            let parent = n.parent.map_or(Matrix4::identity(), |parent| self.world_matrix(parent));
+            n.world.set(parent * n.transform.matrix());
+            n.dirty.set(false);
+        }
+        n.world.get()
+    }
+
+    pub fn is_visible(&self, node: NodeId) -> bool {
+        self.node(node).visible
+    }
+
+    /// Hides or shows `node` and everything below it.
+    pub fn set_visible(&mut self, node: NodeId, visible: bool) {
+        self.node_mut(node).visible = visible;
+    }
+
+    pub fn geometry(&self, node: NodeId) -> Option<&Geometry> {
+        self.node(node).geometry.as_ref()
+    }
+
+    pub fn geometry_mut(&mut self, node: NodeId) -> Option<&mut Geometry> {
+        self.node_mut(node).geometry.as_mut()
+    }
+
+    pub fn set_geometry(&mut self, node: NodeId, geometry: Option<Geometry>) {
+        self.node_mut(node).geometry = geometry;
+    }
+
+    /// Uploads `object` again as the geometry of `node`, leaving every other node alone.
+    pub fn update(&mut self, node: NodeId, object: &dyn Draw, app: &App) {
+        self.set_geometry(node, Some(object.draw(app)));
+    }
+
+    /// The geometry of every visible node, placed by its node's world matrix, parents
+    /// before their children.
+    pub(crate) fn visible_geometry(&self) -> Vec<Geometry> {
+        let mut list = Vec::new();
+        let mut stack: Vec<NodeId> = self.roots.iter().rev().copied().collect();
+        while let Some(id) = stack.pop() {
+            let node = self.node(id);
+            if !node.visible {
+                continue;
+            }
+            if let Some(geometry) = &node.geometry {
+                let mut geometry = geometry.clone();
+                geometry.set_parent_matrix(self.world_matrix(id));
+                list.push(geometry);
+            }
+            stack.extend(node.children.iter().rev());
+        }
+        list
+    }
+
+    fn mark_dirty(&self, node: NodeId) {
+        let mut stack = vec![node];
+        while let Some(id) = stack.pop() {
+            let n = self.node(id);
+            // Everything below a dirty node is dirty already.
+            if !n.dirty.replace(true) {
+                stack.extend(&n.children);
+            }
+        }
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        self.slots.get(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_ref())
+            .expect("node was removed from the scene")
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        self.slots.get_mut(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_mut())
+            .expect("node was removed from the scene")
+    }
+}
+
+fn upload(object: &dyn Draw, app: &App) -> Geometry {
+    if object.is_animated() {
+        log::warn!("An animated surface added to a scene stays as it is now; update its node every frame to animate it");
+    }
+    object.draw(app)
+}
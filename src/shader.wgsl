@@ -1,26 +1,109 @@
+struct CameraUniform {
+    view_position: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    viewport: vec2<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct Light {
+    // w is 0 for directional lights, where xyz points towards the light, and 1 for point lights.
+    position: vec4<f32>,
+    color: vec4<f32>,
+};
+
+struct LightUniform {
+    ambient: vec4<f32>,
+    specular: f32,
+    shininess: f32,
+    count: u32,
+    lights: array<Light, 8>,
+};
+@group(0) @binding(1)
+var<uniform> lighting: LightUniform;
+
+struct ModelUniform {
+    model: mat4x4<f32>,
+    normal: mat4x4<f32>,
+    // Non-zero for overlays whose positions are already in normalized device coordinates.
+    screen_space: u32,
+};
+@group(1) @binding(0)
+var<uniform> model_uniform: ModelUniform;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) color: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+};
+
+struct InstanceInput {
+    @location(5) model_matrix_0: vec4<f32>,
+    @location(6) model_matrix_1: vec4<f32>,
+    @location(7) model_matrix_2: vec4<f32>,
+    @location(8) model_matrix_3: vec4<f32>,
+    @location(9) color: vec4<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec3<f32>,
+    @location(1) world_position: vec3<f32>,
+    @location(2) world_normal: vec3<f32>,
 };
 
 @vertex
 fn vs_main(
     model: VertexInput,
+    instance: InstanceInput,
 ) -> VertexOutput {
+    let instance_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+    let world_position = model_uniform.model * instance_matrix * vec4<f32>(model.position, 1.0);
+    // Instances are assumed to be scaled uniformly, so their rotation part transforms normals as is.
+    let instance_normal = instance_matrix * vec4<f32>(model.normal, 0.0);
+
     var out: VertexOutput;
-    out.color = model.color;
-    out.clip_position = vec4<f32>(model.position, 1.0);
+    out.color = mix(model.color, instance.color.rgb, instance.color.a);
+    out.world_position = world_position.xyz;
+    out.world_normal = (model_uniform.normal * instance_normal).xyz;
+    if (model_uniform.screen_space != 0u) {
+        out.clip_position = world_position;
+    } else {
+        out.clip_position = camera.view_proj * world_position;
+    }
     return out;
 }
 
 // Fragment shader
 
 @fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color, 1.0);
-}
\ No newline at end of file
+fn fs_main(in: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    // Vertices without a normal, such as those of lines, are drawn unlit.
+    if (dot(in.world_normal, in.world_normal) < 1e-12) {
+        return vec4<f32>(in.color, 1.0);
+    }
+
+    var normal = normalize(in.world_normal);
+    if (!front_facing) {
+        normal = -normal;
+    }
+    let view_dir = normalize(camera.view_position.xyz - in.world_position);
+
+    var light = lighting.ambient.rgb * in.color;
+    for (var i = 0u; i < lighting.count; i += 1u) {
+        let l = lighting.lights[i];
+        let light_dir = normalize(l.position.xyz - in.world_position * l.position.w);
+        let half_dir = normalize(view_dir + light_dir);
+
+        let diffuse = max(dot(normal, light_dir), 0.0);
+        let specular = pow(max(dot(normal, half_dir), 0.0), lighting.shininess) * lighting.specular;
+        light += l.color.rgb * (diffuse * in.color + specular);
+    }
+
+    return vec4<f32>(light, 1.0);
+}
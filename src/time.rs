@@ -0,0 +1,76 @@
+use web_time::Instant;
+
+/// The most fixed updates run in a single frame. If the simulation falls further
+/// behind than this the remaining time is dropped instead of trying to catch up,
+/// which would only make the next frame slower still.
+pub const MAX_UPDATES_PER_FRAME: u32 = 8;
+
+/// Tracks simulated time, the step of the current update and the number of rendered frames.
+#[derive(Debug)]
+pub struct Clock {
+    last_tick: Option<Instant>,
+    time: f64,
+    delta: f32,
+    frame_count: u64,
+    accumulator: f32,
+}
+
+impl Clock {
+    pub fn new() -> Clock {
+        Clock {
+            last_tick: None,
+            time: 0.,
+            delta: 0.,
+            frame_count: 0,
+            accumulator: 0.,
+        }
+    }
+
+    /// Real seconds since the previous call, 0 the first time.
+    pub fn tick(&mut self) -> f32 {
+        let now = Instant::now();
+        let elapsed = self.last_tick.map_or(0., |last| (now - last).as_secs_f32());
+        self.last_tick = Some(now);
+        elapsed
+    }
+
+    pub fn advance(&mut self, delta: f32) {
+        self.time += delta as f64;
+        self.delta = delta;
+    }
+
+    /// Adds `elapsed` seconds to the accumulator and returns how many fixed steps of
+    /// `step` seconds are due.
+    pub fn fixed_steps(&mut self, elapsed: f32, step: f32) -> u32 {
+        self.accumulator += elapsed;
+        let due = (self.accumulator / step).floor();
+        if due > MAX_UPDATES_PER_FRAME as f32 {
+            self.accumulator = 0.;
+            return MAX_UPDATES_PER_FRAME;
+        }
+        self.accumulator -= due * step;
+        due as u32
+    }
+
+    pub fn end_frame(&mut self) {
+        self.frame_count += 1;
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time as f32
+    }
+
+    pub fn delta(&self) -> f32 {
+        self.delta
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Clock {
+        Clock::new()
+    }
+}
@@ -0,0 +1,76 @@
+use cgmath::{Matrix, Matrix4, Quaternion, Vector3, One, SquareMatrix};
+
+/// Translation, rotation and non-uniform scale applied to a piece of geometry.
+///
+/// The resulting matrix always scales first, then rotates, then translates,
+/// independent of the order the builder methods are called in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Transform {
+    pub fn new() -> Transform {
+        Transform {
+            translation: Vector3::new(0., 0., 0.),
+            rotation: Quaternion::one(),
+            scale: Vector3::new(1., 1., 1.),
+        }
+    }
+
+    pub fn translate(&mut self, translation: impl Into<Vector3<f32>>) -> &mut Transform {
+        self.translation += translation.into();
+        self
+    }
+
+    /// Applies `rotation` after the current rotation.
+    pub fn rotate(&mut self, rotation: impl Into<Quaternion<f32>>) -> &mut Transform {
+        self.rotation = rotation.into() * self.rotation;
+        self
+    }
+
+    /// Multiplies the current scale component-wise by `scale`.
+    pub fn scale(&mut self, scale: impl Into<Vector3<f32>>) -> &mut Transform {
+        let scale = scale.into();
+        self.scale = Vector3::new(self.scale.x * scale.x, self.scale.y * scale.y, self.scale.z * scale.z);
+        self
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::new()
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct ModelUniform {
+    model: [[f32; 4]; 4],
+    // Inverse transpose of the model matrix, which keeps normals perpendicular
+    // to their surface under non-uniform scaling.
+    normal: [[f32; 4]; 4],
+    // Non-zero when positions are already in normalized device coordinates.
+    screen_space: u32,
+    _padding: [u32; 3],
+}
+
+impl ModelUniform {
+    pub fn new(model: Matrix4<f32>, screen_space: bool) -> ModelUniform {
+        let normal = model.invert().unwrap_or(Matrix4::identity()).transpose();
+        ModelUniform {
+            model: model.into(),
+            normal: normal.into(),
+            screen_space: screen_space as u32,
+            _padding: [0; 3],
+        }
+    }
+}
@@ -1,14 +1,29 @@
+use cgmath::{InnerSpace, Vector3};
+
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex{
     pub position: [f32; 3],
     pub color: [f32; 3],
+    pub normal: [f32; 3],
+    /// Texture coordinates, `[0, 0]` for geometry that doesn't define any.
+    pub uv: [f32; 2],
 }
 
 impl Vertex{
+    /// Creates a vertex without a normal. Geometry made only of such vertices is drawn unlit.
     pub fn new(position: [f32; 3], color: [f32; 3]) -> Vertex {
-        Vertex { position, color }
+        Vertex { position, color, normal: [0., 0., 0.], uv: [0., 0.] }
+    }
+
+    pub fn with_normal(position: [f32; 3], color: [f32; 3], normal: [f32; 3]) -> Vertex {
+        Vertex { position, color, normal, uv: [0., 0.] }
+    }
+
+    pub fn with_uv(mut self, uv: [f32; 2]) -> Vertex {
+        self.uv = uv;
+        self
     }
 
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
@@ -25,8 +40,40 @@ impl Vertex{
                     offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x2,
                 }
             ]
         }
     }
 }
+
+/// Sets every vertex normal to the area weighted average of the normals of the
+/// triangles around it. Triangles are expected to wind counter-clockwise.
+pub fn compute_normals(vertices: &mut [Vertex], indices: &[u32]) {
+    let mut normals = vec![Vector3::new(0., 0., 0.); vertices.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize);
+        let pa = Vector3::from(vertices[a].position);
+        let pb = Vector3::from(vertices[b].position);
+        let pc = Vector3::from(vertices[c].position);
+        // The cross product's length is twice the triangle area, which weights the average.
+        let face_normal = (pb - pa).cross(pc - pa);
+        normals[a] += face_normal;
+        normals[b] += face_normal;
+        normals[c] += face_normal;
+    }
+    for (vertex, normal) in vertices.iter_mut().zip(normals) {
+        if normal.magnitude2() > 0. {
+            vertex.normal = normal.normalize().into();
+        }
+    }
+}
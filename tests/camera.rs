@@ -0,0 +1,62 @@
+use draw3d::camera::{Camera, OPENGL_TO_WGPU_MATRIX};
+use draw3d::cgmath::{MetricSpace, Point3, Vector4};
+
+fn project(camera: &Camera, p: [f32; 3]) -> [f32; 3] {
+    let clip = camera.view_projection_matrix() * Vector4::new(p[0], p[1], p[2], 1.);
+    [clip.x / clip.w, clip.y / clip.w, clip.z / clip.w]
+}
+
+fn close(a: [f32; 3], b: [f32; 3]) -> bool {
+    (0..3).all(|k| (a[k] - b[k]).abs() < 1e-4)
+}
+
+#[test]
+fn depth_is_squashed_into_zero_to_one() {
+    // OpenGL's -1 and 1 become wgpu's 0 and 1, leaving x and y alone.
+    let near = OPENGL_TO_WGPU_MATRIX * Vector4::new(0.3, -0.4, -1., 1.);
+    let far = OPENGL_TO_WGPU_MATRIX * Vector4::new(0.3, -0.4, 1., 1.);
+    assert_eq!(near, Vector4::new(0.3, -0.4, 0., 1.));
+    assert_eq!(far, Vector4::new(0.3, -0.4, 1., 1.));
+}
+
+#[test]
+fn the_target_is_in_the_middle_of_the_view() {
+    let mut camera = Camera::new(2.);
+    camera.look_at((0., -5., 0.), (0., 0., 0.));
+    let [x, y, z] = project(&camera, [0., 0., 0.]);
+    assert!(x.abs() < 1e-6 && y.abs() < 1e-6);
+    assert!(z > 0. && z < 1.);
+
+    // The near and far planes land on the ends of the depth range.
+    assert!(close(project(&camera, [0., -5. + camera.znear, 0.]), [0., 0., 0.]));
+    assert!(close(project(&camera, [0., -5. + camera.zfar, 0.]), [0., 0., 1.]));
+
+    // Up is z, and x runs to the right when looking along +y.
+    let [x, y, _] = project(&camera, [1., 0., 1.]);
+    assert!(x > 0. && y > 0.);
+}
+
+#[test]
+fn orthographic_views_show_height_units_whatever_the_distance() {
+    let mut camera = Camera::new(2.);
+    camera.look_at((0., -5., 0.), (0., 0., 0.)).orthographic(4.);
+    let [x, y, _] = project(&camera, [4., 0., 2.]);
+    assert!(close([x, y, 0.], [1., 1., 0.]));
+    // Further away, the same offset still reaches the corner.
+    let [x, y, _] = project(&camera, [-4., 3., -2.]);
+    assert!(close([x, y, 0.], [-1., -1., 0.]));
+}
+
+#[test]
+fn unprojecting_finds_the_point_on_the_target_plane() {
+    let mut camera = Camera::new(1.5);
+    camera.look_at((1., -4., 2.), (1., 0., 2.));
+    let point = Point3::new(1.5, 0., 2.25);
+    let [x, y, _] = project(&camera, point.into());
+    assert!(camera.unproject(x, y).unwrap().distance(point) < 1e-4);
+    assert!(camera.unproject(0., 0.).unwrap().distance(camera.target) < 1e-4);
+
+    camera.orthographic(3.);
+    let [x, y, _] = project(&camera, point.into());
+    assert!(camera.unproject(x, y).unwrap().distance(point) < 1e-4);
+}
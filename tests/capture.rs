@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use draw3d::capture::{FrameSequence, Image};
+
+#[test]
+fn frames_are_numbered_by_their_place_in_the_sequence() {
+    let sequence = FrameSequence::new(3, "frames");
+    let paths: Vec<Option<PathBuf>> = (0..7).map(|frame| sequence.path(frame)).collect();
+    assert_eq!(paths, [
+        Some(PathBuf::from("frames/frame_00000.png")),
+        None,
+        None,
+        Some(PathBuf::from("frames/frame_00001.png")),
+        None,
+        None,
+        Some(PathBuf::from("frames/frame_00002.png")),
+    ]);
+
+    // Saving every 0th frame saves them all.
+    let every = FrameSequence::new(0, "frames");
+    assert_eq!(every.path(4), Some(PathBuf::from("frames/frame_00004.png")));
+}
+
+#[test]
+fn only_frames_in_the_sequence_are_saved() {
+    let directory = std::env::temp_dir().join(format!("draw3d_capture_{}", std::process::id()));
+    let sequence = FrameSequence::new(2, &directory);
+    let image = Image { width: 2, height: 1, data: vec![255, 0, 0, 255, 0, 0, 255, 255] };
+
+    // Frames outside the sequence don't even make the directory.
+    sequence.save(1, &image).unwrap();
+    assert!(!directory.exists());
+
+    sequence.save(4, &image).unwrap();
+    let saved = std::fs::read(directory.join("frame_00002.png")).unwrap();
+    assert!(saved.starts_with(b"\x89PNG"));
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,67 @@
+use draw3d::colormap::Colormap;
+use draw3d::geometry::{Coloring, Domain, ParametricSurface, PolyLine};
+use draw3d::vertex::Vertex;
+
+fn plane() -> ParametricSurface {
+    let range = Domain::linspace(0., 1., 4);
+    ParametricSurface::new(|u, v| [2. * u, 3. * v, 0.], range.clone(), range)
+}
+
+fn assert_color(actual: [f32; 3], expected: [f32; 3]) {
+    assert!((0..3).all(|k| (actual[k] - expected[k]).abs() < 1e-5), "{actual:?} != {expected:?}");
+}
+
+#[test]
+fn surface_colors() {
+    let (vertices, _) = plane().coloring([1., 0., 0.]).tessellate(0.);
+    assert!(vertices.iter().all(|v| v.color == [1., 0., 0.]));
+
+    let (vertices, _) = plane().coloring(Coloring::by_parameters(|u, v| [u, v, 0.])).tessellate(0.);
+    for v in vertices.iter() {
+        assert_color(v.color, [v.position[0] / 2., v.position[1] / 3., 0.]);
+    }
+
+    let (vertices, _) = plane().coloring(Coloring::by_position(|p| p)).tessellate(0.);
+    assert!(vertices.iter().all(|v| v.color == v.position));
+}
+
+#[test]
+fn scalar_coloring_spans_the_colormap() {
+    let colormap = Colormap::viridis();
+    let (vertices, _) = plane().coloring(Coloring::by_scalar(|u, _, _| u, colormap.clone())).tessellate(0.);
+    for v in vertices.iter() {
+        assert_color(v.color, colormap.map(v.position[0] / 2.));
+    }
+
+    let coloring = Coloring::by_scalar(|u, _, _| u, colormap.clone()).range(0., 2.);
+    let (vertices, _) = plane().coloring(coloring).tessellate(0.);
+    for v in vertices.iter() {
+        assert_color(v.color, colormap.map(v.position[0] / 4.));
+    }
+}
+
+#[test]
+fn polyline_joins_consecutive_vertices() {
+    let points = [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.]];
+    let (vertices, indices) = PolyLine::from_points(&points).tessellate();
+    assert_eq!(vertices.len(), 3);
+    assert_eq!(indices, [0, 1, 1, 2]);
+
+    let mut line = PolyLine::new(Vec::new());
+    line.push(Vertex::new([0., 0., 0.], [1., 1., 1.]));
+    line.push(Vertex::new([1., 0., 0.], [1., 1., 1.]));
+    assert_eq!(line.tessellate().1, [0, 1]);
+}
+
+#[test]
+fn polyline_coloring_overrides_vertex_colors() {
+    let points = [[0., 0., 0.], [1., 0., 0.], [2., 0., 0.]];
+    let (vertices, _) = PolyLine::from_points(&points)
+        .coloring(Coloring::by_parameters(|u, _| [u, 0., 0.]))
+        .tessellate();
+    let reds: Vec<f32> = vertices.iter().map(|v| v.color[0]).collect();
+    assert_eq!(reds, [0., 0.5, 1.]);
+
+    let (vertices, _) = PolyLine::new(vec![Vertex::new([0., 0., 0.], [0., 1., 0.])]).tessellate();
+    assert_eq!(vertices[0].color, [0., 1., 0.]);
+}
@@ -0,0 +1,9 @@
+//! Checks on meshes shared by the integration tests.
+
+/// The volume enclosed by the triangles, positive when they face outwards.
+pub fn signed_volume(positions: &[[f32; 3]], indices: &[u32]) -> f32 {
+    indices.chunks_exact(3).map(|t| {
+        let [a, b, c] = [t[0], t[1], t[2]].map(|i| positions[i as usize]);
+        (a[0] * (b[1] * c[2] - b[2] * c[1]) + a[1] * (b[2] * c[0] - b[0] * c[2]) + a[2] * (b[0] * c[1] - b[1] * c[0])) / 6.
+    }).sum()
+}
@@ -0,0 +1,96 @@
+use std::f32::consts::PI;
+
+use draw3d::camera::{Camera, Projection};
+use draw3d::cgmath::{InnerSpace, MetricSpace, Point3, Vector3};
+use draw3d::controller::OrbitController;
+
+fn close(a: Point3<f32>, b: Point3<f32>) -> bool {
+    a.distance(b) < 1e-4
+}
+
+fn camera() -> Camera {
+    let mut camera = Camera::new(1.);
+    camera.look_at((0., -2., 0.), (0., 0., 0.));
+    camera
+}
+
+#[test]
+fn orbiting_keeps_the_distance_to_the_target() {
+    let mut controller = OrbitController::new();
+    let mut camera = camera();
+    // A quarter turn to the left around the up axis.
+    controller.orbit(&mut camera, -PI / 2. / controller.rotate_speed, 0.);
+    assert!(close(camera.eye, Point3::new(2., 0., 0.)));
+
+    // Tilting far past the top stops just short of it.
+    controller.orbit(&mut camera, 0., 1e4);
+    assert!((camera.eye.distance(camera.target) - 2.).abs() < 1e-4);
+    assert!(camera.eye.z > 1.99 && camera.eye.x > 0.);
+    assert_eq!(camera.target, Point3::new(0., 0., 0.));
+}
+
+#[test]
+fn a_camera_on_the_up_axis_can_still_be_moved() {
+    let mut controller = OrbitController::new();
+    let mut camera = Camera::new(1.);
+    camera.look_at((0., 0., 3.), (0., 0., 0.));
+    controller.orbit(&mut camera, 10., 10.);
+    assert!(camera.eye.x.is_finite() && camera.eye.y.is_finite() && camera.eye.z.is_finite());
+    assert!((camera.eye.distance(camera.target) - 3.).abs() < 1e-4);
+    // It's tipped off the axis, so the next drags have a direction to work with.
+    assert!((camera.eye - camera.target).angle(camera.up).0 > 0.);
+
+    let mut camera = Camera::new(1.);
+    camera.look_at((0., 0., 3.), (0., 0., 0.));
+    controller.pan(&mut camera, 10., 10., 100);
+    assert!(camera.target.x.is_finite() && camera.target.y.is_finite());
+    assert!((camera.eye - camera.target - Vector3::new(0., 0., 3.)).magnitude() < 1e-4);
+}
+
+#[test]
+fn panning_moves_the_target_with_the_cursor() {
+    let mut controller = OrbitController::new();
+    let mut camera = camera();
+    camera.orthographic(2.);
+    // Half the viewport to the right shows what was half a view to the left.
+    controller.pan(&mut camera, 50., 0., 100);
+    assert!(close(camera.target, Point3::new(-1., 0., 0.)));
+    assert!(close(camera.eye, Point3::new(-1., -2., 0.)));
+
+    controller.pan(&mut camera, 0., 25., 100);
+    assert!(close(camera.target, Point3::new(-1., 0., 0.5)));
+}
+
+#[test]
+fn zooming_scales_the_distance_or_the_view() {
+    let mut controller = OrbitController::new();
+    let mut camera = camera();
+    controller.zoom(&mut camera, 1.);
+    assert!((camera.eye.distance(camera.target) - 1.8).abs() < 1e-5);
+    // Zooming never goes through the near plane.
+    controller.zoom(&mut camera, 1000.);
+    assert!(camera.eye.distance(camera.target) > camera.znear);
+
+    camera.orthographic(4.);
+    controller.zoom(&mut camera, -1.);
+    assert_eq!(camera.projection, Projection::Orthographic { height: 4. / 0.9 });
+}
+
+#[test]
+fn reset_restores_the_camera_before_the_first_move() {
+    let mut controller = OrbitController::new();
+    let mut camera = camera();
+    // Without any move there's nothing to go back to.
+    controller.reset(&mut camera);
+    assert!(close(camera.eye, Point3::new(0., -2., 0.)));
+
+    controller.orbit(&mut camera, 30., 20.);
+    controller.pan(&mut camera, 5., 5., 100);
+    controller.zoom(&mut camera, 2.);
+    camera.aspect = 2.;
+    controller.reset(&mut camera);
+    assert_eq!(camera.eye, Point3::new(0., -2., 0.));
+    assert_eq!(camera.target, Point3::new(0., 0., 0.));
+    // The window may have been resized since.
+    assert_eq!(camera.aspect, 2.);
+}
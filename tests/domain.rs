@@ -0,0 +1,64 @@
+use draw3d::geometry::{Boundary, Domain};
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len(), "{actual:?} != {expected:?}");
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-5 * e.abs().max(1.), "{actual:?} != {expected:?}");
+    }
+}
+
+#[test]
+fn linspace_endpoint() {
+    assert_close(&Domain::linspace(0., 1., 5).values(), &[0., 0.25, 0.5, 0.75, 1.]);
+    assert_close(&Domain::linspace(0., 1., 4).endpoint(false).values(), &[0., 0.25, 0.5, 0.75]);
+    assert_close(&Domain::linspace(2., 3., 1).values(), &[2.]);
+}
+
+#[test]
+fn periodic_leaves_out_the_endpoint() {
+    let domain = Domain::linspace(0., 1., 4).periodic();
+    assert_eq!(domain.boundary(), Boundary::Periodic);
+    assert_close(&domain.values(), &[0., 0.25, 0.5, 0.75]);
+}
+
+#[test]
+fn arange_stops_before_stop() {
+    assert_close(&Domain::arange(0., 1., 0.25).values(), &[0., 0.25, 0.5, 0.75]);
+    assert_close(&Domain::arange(0., 1., 0.25).endpoint(true).values(), &[0., 0.25, 0.5, 0.75, 1.]);
+    assert_close(&Domain::arange(1., 0., -0.5).values(), &[1., 0.5]);
+    assert!(Domain::arange(0., 1., -0.5).is_empty());
+}
+
+#[test]
+fn logspace_is_geometric() {
+    assert_close(&Domain::logspace(1., 1000., 4).values(), &[1., 10., 100., 1000.]);
+    assert_close(&Domain::logspace(-1., -100., 3).values(), &[-1., -10., -100.]);
+}
+
+#[test]
+#[should_panic(expected = "logspace needs non-zero ends of the same sign")]
+fn logspace_across_zero_is_refused() {
+    let _ = Domain::logspace(-1., 100., 3);
+}
+
+#[test]
+#[should_panic(expected = "logspace needs non-zero ends of the same sign")]
+fn logspace_from_zero_is_refused() {
+    let _ = Domain::logspace(0., 100., 3);
+}
+
+#[test]
+fn chebyshev_nodes() {
+    let lobatto = Domain::chebyshev(-1., 1., 3).values();
+    assert_close(&lobatto, &[-1., 0., 1.]);
+
+    let gauss = Domain::chebyshev(0., 2., 2).endpoint(false).values();
+    let x = 1. - std::f32::consts::FRAC_1_SQRT_2;
+    assert_close(&gauss, &[x, 2. - x]);
+}
+
+#[test]
+fn explicit_samples() {
+    let domain: Domain = vec![0., 0.1, 1.].into();
+    assert_close(&domain.endpoint(false).values(), &[0., 0.1, 1.]);
+}
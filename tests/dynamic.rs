@@ -0,0 +1,69 @@
+use draw3d::geometry::dynamic::SlotWrites;
+use draw3d::geometry::DynamicSlots;
+
+#[test]
+fn lines_write_after_what_they_hold_and_grow_to_a_power_of_two() {
+    let mut slots = DynamicSlots::line(4);
+    assert_eq!(slots.extend(3), SlotWrites { grow_to: 4, kept: 0, writes: vec![(0, 0..3)] });
+    assert_eq!(slots.index_range(), 0..4);
+
+    // Two more don't fit, so the buffers grow and the three pushed are copied over.
+    assert_eq!(slots.extend(2), SlotWrites { grow_to: 8, kept: 3, writes: vec![(3, 0..2)] });
+    assert_eq!(slots.capacity(), 8);
+    assert_eq!(slots.len(), 5);
+    assert_eq!(slots.index_range(), 0..8);
+
+    assert_eq!(slots.extend(20).grow_to, 32);
+    assert_eq!(slots.extend(0), SlotWrites { grow_to: 32, kept: 25, writes: vec![] });
+}
+
+#[test]
+fn rings_wrap_around_once_full() {
+    let mut slots = DynamicSlots::ring(4);
+    assert_eq!(slots.extend(3).writes, [(0, 0..3)]);
+    assert_eq!(slots.len(), 3);
+    assert_eq!(slots.index_range(), 0..4);
+
+    // The fourth fills the ring, which then draws from slot 0 to slot 3.
+    assert_eq!(slots.extend(1).writes, [(3, 0..1)]);
+    assert_eq!(slots.index_range(), 0..6);
+
+    // The next two overwrite the oldest, and the window moves past them.
+    assert_eq!(slots.extend(2).writes, [(0, 0..2)]);
+    assert_eq!(slots.len(), 4);
+    assert_eq!(slots.capacity(), 4);
+    assert_eq!(slots.index_range(), 4..10);
+
+    // Across the end of the buffer the write splits in two.
+    assert_eq!(slots.extend(3).writes, [(2, 0..2), (0, 2..3)]);
+    assert_eq!(slots.index_range(), 2..8);
+}
+
+#[test]
+fn extending_a_ring_by_more_than_it_holds_writes_only_the_last() {
+    let mut slots = DynamicSlots::ring(4);
+    slots.extend(1);
+    // Of ten new vertices only the last four are kept, starting after the one there.
+    let writes = slots.extend(10);
+    assert_eq!(writes.grow_to, 4);
+    assert_eq!(writes.writes, [(3, 6..7), (0, 7..10)]);
+    assert_eq!(slots.len(), 4);
+    assert_eq!(slots.index_range(), 6..12);
+}
+
+#[test]
+fn clearing_starts_again_from_the_first_slot() {
+    let mut slots = DynamicSlots::ring(3);
+    slots.extend(5);
+    slots.clear();
+    assert!(slots.is_empty());
+    assert_eq!(slots.index_range(), 0..0);
+    assert_eq!(slots.extend(2).writes, [(0, 0..2)]);
+    assert_eq!(slots.index_range(), 0..2);
+
+    let mut line = DynamicSlots::line(2);
+    line.extend(5);
+    line.clear();
+    // The grown capacity is kept for the next vertices.
+    assert_eq!(line.extend(1), SlotWrites { grow_to: 8, kept: 0, writes: vec![(0, 0..1)] });
+}
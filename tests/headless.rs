@@ -0,0 +1,39 @@
+use draw3d::app::{App, AppBuilder};
+use draw3d::geometry::primitives::UvSphere;
+
+/// Whether wgpu finds any adapter to render with, hardware or fallback.
+fn adapter_available() -> bool {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    [false, true].into_iter().any(|force_fallback_adapter| {
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            force_fallback_adapter,
+            ..Default::default()
+        })).is_some()
+    })
+}
+
+fn view(app: &mut App, _: &()) {
+    app.camera_mut().look_at((0., -3., 0.), (0., 0., 0.));
+    let mut draw = app.draw();
+    draw.update_background_color((1., 0., 0.));
+    draw.add(&UvSphere::new(0.8), app);
+    app.draw_to_frame(draw);
+}
+
+#[test]
+fn frames_render_without_a_window() {
+    if !adapter_available() {
+        eprintln!("no adapter to render with, skipping");
+        return;
+    }
+    let images = pollster::block_on(AppBuilder::app(|_| ()).view(view).render_to_texture(64, 48, 2));
+    assert_eq!(images.len(), 2);
+    for image in &images {
+        assert_eq!((image.width, image.height), (64, 48));
+        assert_eq!(image.data.len(), 64 * 48 * 4);
+        // The background fills the corners and the sphere covers the middle.
+        assert_eq!(image.pixel(0, 0), [255, 0, 0, 255]);
+        assert_ne!(image.pixel(32, 24), [255, 0, 0, 255]);
+        assert!(image.pixel(32, 24)[2] > 0);
+    }
+}
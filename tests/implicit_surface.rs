@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use draw3d::geometry::ImplicitSurface;
+use draw3d::vertex::Vertex;
+
+fn sphere([x, y, z]: [f32; 3]) -> f32 {
+    x * x + y * y + z * z - 1.
+}
+
+fn torus([x, y, z]: [f32; 3]) -> f32 {
+    let ring = (x * x + y * y).sqrt() - 1.;
+    ring * ring + z * z - 0.4 * 0.4
+}
+
+/// Checks every edge is shared by two triangles going opposite ways and returns the
+/// Euler characteristic.
+fn closed_euler_characteristic(vertices: &[Vertex], indices: &[u32]) -> i64 {
+    let mut edges = HashMap::new();
+    for t in indices.chunks_exact(3) {
+        for (a, b) in [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])] {
+            *edges.entry((a, b)).or_insert(0) += 1;
+        }
+    }
+    for (&(a, b), &count) in edges.iter() {
+        assert_eq!(count, 1, "edge {a}->{b} is used {count} times in the same direction");
+        assert!(edges.contains_key(&(b, a)), "edge {a}->{b} has no opposite edge");
+    }
+    vertices.len() as i64 - edges.len() as i64 / 2 + indices.len() as i64 / 3
+}
+
+fn assert_normals_match_winding(vertices: &[Vertex], indices: &[u32]) {
+    for t in indices.chunks_exact(3) {
+        let [a, b, c] = [t[0], t[1], t[2]].map(|i| vertices[i as usize]);
+        let ab = [0, 1, 2].map(|k| b.position[k] - a.position[k]);
+        let ac = [0, 1, 2].map(|k| c.position[k] - a.position[k]);
+        let n = [ab[1] * ac[2] - ab[2] * ac[1], ab[2] * ac[0] - ab[0] * ac[2], ab[0] * ac[1] - ab[1] * ac[0]];
+        let dot: f32 = (0..3).map(|k| n[k] * (a.normal[k] + b.normal[k] + c.normal[k])).sum();
+        assert!(dot >= 0., "triangle {t:?} faces against its normals");
+    }
+}
+
+#[test]
+fn sphere_is_closed_and_faces_outwards() {
+    let (vertices, indices) = ImplicitSurface::new(sphere, [-1.5; 3], [1.5; 3]).resolution(20).tessellate();
+
+    assert_eq!(closed_euler_characteristic(&vertices, &indices), 2);
+    assert_normals_match_winding(&vertices, &indices);
+    for v in vertices.iter() {
+        let r = v.position.iter().map(|p| p * p).sum::<f32>().sqrt();
+        assert!((r - 1.).abs() < 0.02, "vertex {:?} is off the sphere", v.position);
+        let dot: f32 = (0..3).map(|k| v.normal[k] * v.position[k]).sum();
+        assert!(dot > 0.99);
+    }
+}
+
+#[test]
+fn torus_has_a_hole() {
+    let (vertices, indices) = ImplicitSurface::new(torus, [-1.6, -1.6, -0.6], [1.6, 1.6, 0.6])
+        .resolution_xyz([40, 40, 15])
+        .tessellate();
+
+    assert_eq!(closed_euler_characteristic(&vertices, &indices), 0);
+    assert_normals_match_winding(&vertices, &indices);
+}
+
+#[test]
+fn every_corner_configuration_is_closed() {
+    // Each configuration of the middle cube of a 4x4x4 grid, surrounded by samples
+    // outside the surface so it can't run off the edges.
+    for case in 0..256 {
+        let values = (0..64).map(|i| {
+            let [x, y, z] = [i % 4, i / 4 % 4, i / 16];
+            let corner = [x, y, z].iter().all(|&c| c == 1 || c == 2);
+            let bit = (x - 1) + 2 * (y - 1).min(1) + 4 * (z - 1).min(1);
+            if corner && case & (1 << bit) != 0 { -1. } else { 1. }
+        }).collect();
+        let (vertices, indices) = ImplicitSurface::from_grid(values, [4; 3], [0.; 3], [1.; 3]).tessellate();
+
+        assert_eq!(indices.is_empty(), case == 0);
+        closed_euler_characteristic(&vertices, &indices);
+        // Closed surfaces facing away from the inside corners enclose a positive volume.
+        let volume: f32 = indices.chunks_exact(3).map(|t| {
+            let [a, b, c] = [t[0], t[1], t[2]].map(|i| vertices[i as usize].position);
+            a[0] * (b[1] * c[2] - b[2] * c[1]) + a[1] * (b[2] * c[0] - b[0] * c[2]) + a[2] * (b[0] * c[1] - b[1] * c[0])
+        }).sum();
+        assert!(case == 0 || volume > 0., "case {case:#010b} encloses a volume of {volume}");
+    }
+}
+
+#[test]
+fn sampled_grid_matches_function() {
+    let n = 17;
+    let coordinate = |i: usize| -1.5 + 3. * i as f32 / (n - 1) as f32;
+    let values = (0..n * n * n).map(|i| sphere([i % n, i / n % n, i / (n * n)].map(coordinate))).collect();
+
+    let (grid_vertices, grid_indices) = ImplicitSurface::from_grid(values, [n; 3], [-1.5; 3], [1.5; 3]).tessellate();
+    let (vertices, indices) = ImplicitSurface::new(sphere, [-1.5; 3], [1.5; 3]).resolution(n - 1).tessellate();
+
+    assert_eq!(grid_indices, indices);
+    for (a, b) in grid_vertices.iter().zip(vertices.iter()) {
+        assert!((0..3).all(|k| (a.position[k] - b.position[k]).abs() < 1e-5));
+        assert!((0..3).map(|k| a.normal[k] * b.normal[k]).sum::<f32>() > 0.95);
+    }
+}
+
+#[test]
+fn iso_level_picks_the_level_set() {
+    let (vertices, _) = ImplicitSurface::new(sphere, [-2.; 3], [2.; 3]).iso_level(3.).tessellate();
+    for v in vertices.iter() {
+        let r = v.position.iter().map(|p| p * p).sum::<f32>().sqrt();
+        assert!((r - 2.).abs() < 0.05, "vertex {:?} is off the sphere of radius 2", v.position);
+    }
+}
@@ -0,0 +1,59 @@
+use draw3d::input::InputState;
+use draw3d::winit::dpi::{PhysicalPosition, PhysicalSize};
+use draw3d::winit::event::{DeviceId, ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent};
+
+fn device() -> DeviceId {
+    // Only used to fill in the events, never to talk to a device.
+    unsafe { DeviceId::dummy() }
+}
+
+fn button(state: ElementState, button: MouseButton) -> WindowEvent {
+    WindowEvent::MouseInput { device_id: device(), state, button }
+}
+
+fn wheel(delta: MouseScrollDelta) -> WindowEvent {
+    WindowEvent::MouseWheel { device_id: device(), delta, phase: TouchPhase::Moved }
+}
+
+#[test]
+fn buttons_are_held_until_released() {
+    let mut input = InputState::new();
+    input.process_event(&button(ElementState::Pressed, MouseButton::Left));
+    input.process_event(&button(ElementState::Pressed, MouseButton::Right));
+    input.process_event(&button(ElementState::Released, MouseButton::Left));
+    assert!(!input.mouse_button_pressed(MouseButton::Left));
+    assert!(input.mouse_button_pressed(MouseButton::Right));
+
+    // Other events and the end of a frame leave them alone.
+    input.process_event(&WindowEvent::Resized(PhysicalSize::new(10, 10)));
+    input.end_frame();
+    assert!(input.mouse_button_pressed(MouseButton::Right));
+
+    // Losing focus forgets everything held, since the release won't arrive.
+    input.process_event(&WindowEvent::Focused(false));
+    assert!(!input.mouse_button_pressed(MouseButton::Right));
+    assert_eq!(input.pressed_keys().count(), 0);
+}
+
+#[test]
+fn the_cursor_is_tracked_while_inside_the_window() {
+    let mut input = InputState::new();
+    assert_eq!(input.mouse_position(), None);
+    input.process_event(&WindowEvent::CursorMoved { device_id: device(), position: PhysicalPosition::new(12.5, 40.) });
+    assert_eq!(input.mouse_position(), Some([12.5, 40.]));
+    input.process_event(&WindowEvent::CursorLeft { device_id: device() });
+    assert_eq!(input.mouse_position(), None);
+}
+
+#[test]
+fn scrolling_adds_up_until_the_end_of_the_frame() {
+    let mut input = InputState::new();
+    input.process_event(&wheel(MouseScrollDelta::LineDelta(0., 1.)));
+    input.process_event(&wheel(MouseScrollDelta::LineDelta(-0.5, 2.)));
+    // 50 pixels make a line.
+    input.process_event(&wheel(MouseScrollDelta::PixelDelta(PhysicalPosition::new(25., -100.))));
+    assert_eq!(input.scroll_delta(), [0., 1.]);
+
+    input.end_frame();
+    assert_eq!(input.scroll_delta(), [0., 0.]);
+}
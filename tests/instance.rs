@@ -0,0 +1,32 @@
+use draw3d::cgmath::{Matrix4, Vector3};
+use draw3d::instance::Instance;
+use draw3d::transform::Transform;
+
+/// The model matrix columns and the color, as the shader reads them.
+fn raw(instance: Instance) -> [f32; 20] {
+    bytemuck::cast(instance.to_raw())
+}
+
+#[test]
+fn raw_instances_hold_the_matrix_by_column_then_the_color() {
+    let mut transform = Transform::new();
+    transform.translate([1., 2., 3.]).scale([2., 2., 2.]);
+    let data = raw(Instance::new(transform).color([0.25, 0.5, 0.75]));
+
+    let columns: [[f32; 4]; 4] = transform.matrix().into();
+    assert_eq!(data[..16], *columns.as_flattened());
+    // The translation is in the last column.
+    assert_eq!(data[12..15], [1., 2., 3.]);
+    assert_eq!(data[16..], [0.25, 0.5, 0.75, 1.]);
+}
+
+#[test]
+fn instances_without_a_color_keep_the_vertex_colors() {
+    let data = raw(Instance::at([4., 5., 6.]));
+    assert_eq!(data[16..], [1., 1., 1., 0.]);
+
+    let columns: [[f32; 4]; 4] = Matrix4::from_translation(Vector3::new(4., 5., 6.)).into();
+    assert_eq!(data[..16], *columns.as_flattened());
+    let identity: [[f32; 4]; 4] = Matrix4::from_scale(1.).into();
+    assert_eq!(raw(Instance::default())[..16], *identity.as_flattened());
+}
@@ -0,0 +1,65 @@
+use draw3d::geometry::{LineCap, LineJoin, LineStyle, PolyLine};
+use draw3d::vertex::Vertex;
+
+fn vertices(points: &[[f32; 3]]) -> Vec<Vertex> {
+    points.iter().map(|&p| Vertex::new(p, [1., 1., 1.])).collect()
+}
+
+const SQUARE: [[f32; 3]; 4] = [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]];
+
+#[test]
+fn open_line_joins_inner_segments_only() {
+    let segments = LineStyle::new(2.).segments(&vertices(&SQUARE), false);
+    assert_eq!(segments.len(), 3);
+    assert_eq!(segments[0].joined, [0, 1]);
+    assert_eq!(segments[1].joined, [1, 1]);
+    assert_eq!(segments[2].joined, [1, 0]);
+    assert_eq!(segments[0].next, SQUARE[2]);
+    // The last segment has nothing after it.
+    assert_eq!(segments[2].next, SQUARE[3]);
+}
+
+#[test]
+fn closed_line_wraps_around() {
+    let segments = LineStyle::new(2.).segments(&vertices(&SQUARE), true);
+    assert_eq!(segments.len(), 4);
+    assert!(segments.iter().all(|s| s.joined == [1, 1]));
+    assert_eq!(segments[3].start, SQUARE[3]);
+    assert_eq!(segments[3].end, SQUARE[0]);
+    assert_eq!(segments[3].next, SQUARE[1]);
+
+    // Repeating the first point at the end doesn't add an empty segment.
+    let mut repeated = SQUARE.to_vec();
+    repeated.push(SQUARE[0]);
+    assert_eq!(LineStyle::new(2.).segments(&vertices(&repeated), true).len(), 4);
+}
+
+#[test]
+fn distances_accumulate_along_the_line() {
+    let points = [[0., 0., 0.], [3., 4., 0.], [3., 4., 0.], [3., 4., 2.]];
+    let segments = LineStyle::new(1.).dashes(0.5, 0.25).segments(&vertices(&points), false);
+    // The repeated point is skipped.
+    assert_eq!(segments.len(), 2);
+    assert_eq!(segments[0].distances, [0., 5.]);
+    assert_eq!(segments[1].distances, [5., 7.]);
+    assert!(segments.iter().all(|s| s.dash == [0.5, 0.25]));
+}
+
+#[test]
+fn style_is_copied_to_every_segment() {
+    let style = LineStyle::new(3.).cap(LineCap::Round).join(LineJoin::Bevel).miter_limit(0.5);
+    assert_eq!(style.miter_limit, 1.);
+    let segments = style.segments(&vertices(&SQUARE), false);
+    assert!(segments.iter().all(|s| {
+        s.width == 3. && s.cap == LineCap::Round as u32 && s.join == LineJoin::Bevel as u32 && s.dash == [0., 0.]
+    }));
+
+    assert!(style.segments(&vertices(&SQUARE[..1]), false).is_empty());
+    assert!(style.segments(&vertices(&[SQUARE[0], SQUARE[0]]), true).is_empty());
+}
+
+#[test]
+fn closed_poly_line_adds_the_closing_segment() {
+    let (_, indices) = PolyLine::from_points(&SQUARE).closed(true).tessellate();
+    assert_eq!(indices, [0, 1, 1, 2, 2, 3, 3, 0]);
+}
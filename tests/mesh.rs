@@ -0,0 +1,152 @@
+use draw3d::cgmath::{InnerSpace, Matrix4, Vector3};
+use draw3d::geometry::primitives::{Cuboid, UvSphere};
+use draw3d::geometry::{Draw, Mesh, MeshError, PolyLine};
+
+mod common;
+use common::signed_volume;
+
+fn tetrahedron() -> Mesh {
+    Mesh::new(
+        vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+        vec![0, 2, 1, 0, 1, 3, 0, 3, 2, 1, 2, 3],
+    )
+}
+
+#[test]
+fn closed_meshes_are_valid() {
+    assert_eq!(tetrahedron().validate(), Ok(()));
+    let mut cube = Cuboid::cube(2.).mesh().unwrap();
+    assert_eq!(cube.validate(), Ok(()));
+    cube.weld(1e-5);
+    assert_eq!(cube.positions.len(), 8);
+    assert_eq!(cube.triangle_count(), 12);
+    assert_eq!(cube.validate(), Ok(()));
+}
+
+#[test]
+fn validate_reports_every_problem() {
+    let mut mesh = tetrahedron();
+    mesh.indices.extend([0, 1, 7, 0, 0, 1, 0, 1, 2]);
+    mesh.colors.pop();
+    let errors = mesh.validate().unwrap_err();
+    assert!(errors.contains(&MeshError::AttributeLength { attribute: "colors", len: 3, positions: 4 }));
+    assert!(errors.contains(&MeshError::IndexOutOfRange { triangle: 4, index: 7 }));
+    assert!(errors.contains(&MeshError::DegenerateTriangle { triangle: 5 }));
+    // The extra triangle is a third one along each of the edges of the base.
+    assert!(errors.contains(&MeshError::NonManifoldEdge { edge: [0, 1], triangles: 3 }));
+
+    mesh.indices.push(0);
+    assert!(mesh.validate().unwrap_err().contains(&MeshError::IncompleteTriangle { indices: 22 }));
+}
+
+#[test]
+fn validate_finds_flipped_triangles_and_collinear_corners() {
+    let mut mesh = tetrahedron();
+    mesh.indices[..3].copy_from_slice(&[0, 1, 2]);
+    let errors = mesh.validate().unwrap_err();
+    assert!(errors.contains(&MeshError::InconsistentWinding { edge: [0, 1] }));
+
+    let flat = Mesh::new(vec![[0., 0., 0.], [1., 1., 1.], [2., 2., 2.]], vec![0, 1, 2]);
+    assert_eq!(flat.validate(), Err(vec![MeshError::DegenerateTriangle { triangle: 0 }]));
+}
+
+#[test]
+fn welding_merges_close_vertices_and_drops_collapsed_triangles() {
+    let mut mesh = Mesh::new(
+        vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [1.0005, 0., 0.], [1., 1., 0.], [0.0004, 0., 0.]],
+        vec![0, 1, 2, 3, 4, 2, 0, 5, 2],
+    );
+    mesh.colors[3] = [1., 0., 0.];
+    mesh.weld(1e-3);
+    assert_eq!(mesh.positions, [[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [1., 1., 0.]]);
+    // The first of the merged vertices keeps its attributes.
+    assert_eq!(mesh.colors[1], [0.6, 0., 0.6]);
+    assert_eq!(mesh.indices, [0, 1, 2, 1, 3, 2]);
+}
+
+#[test]
+fn welding_without_tolerance_merges_exact_duplicates() {
+    let far = 1e13;
+    let mut mesh = Mesh::new(
+        vec![[far, 0., 0.], [far, 1., 0.], [far, 0., 1.], [far, 1., 0.], [far, 1., 1.], [-0., 0., 1e-7]],
+        vec![0, 1, 2, 3, 4, 2, 5, 1, 2],
+    );
+    mesh.weld(0.);
+    assert_eq!(mesh.positions, [[far, 0., 0.], [far, 1., 0.], [far, 0., 1.], [far, 1., 1.], [0., 0., 1e-7]]);
+    assert_eq!(mesh.indices, [0, 1, 2, 1, 3, 2, 4, 1, 2]);
+
+    // Coordinates far outside the grid still weld with a small tolerance.
+    mesh.positions[4] = [far, 0., 1.];
+    mesh.weld(1e-7);
+    assert_eq!(mesh.positions.len(), 4);
+    assert_eq!(mesh.indices, [0, 1, 2, 1, 3, 2]);
+}
+
+#[test]
+fn recomputed_normals_point_outwards() {
+    let mut sphere = UvSphere::new(1.).mesh().unwrap();
+    sphere.normals.iter_mut().for_each(|n| *n = [0.; 3]);
+    // The poles are only in degenerate triangles until they're welded.
+    sphere.weld(1e-5).recompute_normals();
+    assert_eq!(sphere.validate(), Ok(()));
+    for (p, n) in sphere.positions.iter().zip(&sphere.normals) {
+        assert!(Vector3::from(*p).dot(Vector3::from(*n)) > 0.9);
+    }
+}
+
+#[test]
+fn transform_moves_positions_and_keeps_orientation() {
+    let mut mesh = tetrahedron();
+    mesh.recompute_normals();
+    let volume = signed_volume(&mesh.positions, &mesh.indices);
+    mesh.transform(Matrix4::from_translation(Vector3::new(1., 2., 3.)) * Matrix4::from_nonuniform_scale(-2., 1., 1.));
+    let bounds = mesh.bounding_box().unwrap();
+    assert_eq!(bounds.min, [-1., 2., 3.]);
+    assert_eq!(bounds.max, [1., 3., 4.]);
+    assert_eq!(bounds.center(), [0., 2.5, 3.5]);
+    assert_eq!(bounds.size(), [2., 1., 1.]);
+    // Mirrored, but still wound to face outwards.
+    assert!((signed_volume(&mesh.positions, &mesh.indices) - 2. * volume).abs() < 1e-5);
+    let center = Vector3::from(bounds.center());
+    for (p, n) in mesh.positions.iter().zip(&mesh.normals) {
+        assert!((Vector3::from(*p) - center).dot(Vector3::from(*n)) > 0.);
+        assert!((Vector3::from(*n).magnitude() - 1.).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn merge_offsets_indices() {
+    let mut mesh = tetrahedron();
+    let mut other = tetrahedron();
+    other.transform(Matrix4::from_translation(Vector3::new(5., 0., 0.)));
+    mesh.merge(&other);
+    assert_eq!(mesh.positions.len(), 8);
+    assert_eq!(mesh.triangle_count(), 8);
+    assert_eq!(&mesh.indices[12..15], [4, 6, 5]);
+    assert_eq!(mesh.validate(), Ok(()));
+    assert_eq!(mesh.bounding_box().unwrap().max, [6., 1., 1.]);
+
+    assert_eq!(Mesh::default().bounding_box(), None);
+    assert!(PolyLine::from_points(&[[0.; 3], [1.; 3]]).mesh().is_none());
+}
+
+#[test]
+fn triangles_with_missing_vertices_are_dropped_rather_than_panicking() {
+    let mut mesh = tetrahedron();
+    mesh.indices.extend([0, 1, 7]);
+    assert!(mesh.validate().is_err());
+    // The broken triangle adds nothing to the normals.
+    let (mut broken, mut whole) = (mesh.clone(), tetrahedron());
+    broken.recompute_normals();
+    whole.recompute_normals();
+    assert_eq!(broken.normals, whole.normals);
+
+    mesh.weld(1e-6);
+    assert_eq!(mesh.triangle_count(), 4);
+    assert_eq!(mesh.validate_closed(), Ok(()));
+
+    let mut exact = tetrahedron();
+    exact.indices.extend([9, 9, 9]);
+    exact.weld(0.).recompute_normals();
+    assert_eq!(exact.triangle_count(), 4);
+}
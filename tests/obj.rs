@@ -0,0 +1,135 @@
+use draw3d::cgmath::{InnerSpace, Vector3};
+use draw3d::geometry::obj::{self, Materials, Obj, ObjError, ObjGroup};
+use draw3d::geometry::primitives::Torus;
+use draw3d::geometry::{Draw, Mesh, PolyLine};
+
+type Corner = ([f32; 3], [f32; 3], [f32; 3], [f32; 2]);
+
+/// The corners of every triangle, which don't depend on how the vertices are ordered.
+fn corners(mesh: &Mesh) -> Vec<Corner> {
+    let vertices = mesh.vertices();
+    mesh.indices.iter().map(|&i| {
+        let v = vertices[i as usize];
+        (v.position, v.color, v.normal, v.uv)
+    }).collect()
+}
+
+fn read(text: &str) -> Result<Obj, ObjError> {
+    Obj::read(text.as_bytes(), &Materials::new())
+}
+
+#[test]
+fn groups_take_their_material_colors() {
+    let cube = Obj::load("tests/data/cube.obj").unwrap();
+    let names: Vec<&str> = cube.groups.iter().map(|g| g.name.as_str()).collect();
+    assert_eq!(names, ["top", "sides"]);
+
+    let top = cube.group("top").unwrap();
+    assert_eq!(top.material.as_deref(), Some("red"));
+    assert_eq!(top.mesh.triangle_count(), 2);
+    assert_eq!(top.mesh.positions.len(), 4);
+    assert!(top.mesh.colors.iter().all(|&c| c == [0.8, 0.1, 0.1]));
+    assert!(top.mesh.normals.iter().all(|&n| n == [0., 0., 1.]));
+
+    let sides = cube.group("sides").unwrap();
+    assert_eq!(sides.mesh.triangle_count(), 10);
+    assert!(sides.mesh.colors.iter().all(|&c| c == [0.1, 0.2, 0.9]));
+
+    let mut mesh = cube.to_mesh();
+    mesh.weld(1e-6);
+    assert_eq!(mesh.positions.len(), 8);
+    assert_eq!(mesh.validate(), Ok(()));
+}
+
+#[test]
+fn concave_faces_stay_inside_their_outline() {
+    let shapes = Obj::load("tests/data/shapes.obj").unwrap();
+    let l = &shapes.group("L").unwrap().mesh;
+    assert_eq!(l.triangle_count(), 4);
+    let mut area = 0.;
+    for [a, b, c] in l.triangles() {
+        let [a, b, c] = [a, b, c].map(|i| Vector3::from(l.positions[i as usize]));
+        let normal = (b - a).cross(c - a);
+        // Every triangle faces the same way as the outline, so none of them overlap.
+        assert!(normal.z > 0.);
+        area += normal.magnitude() / 2.;
+    }
+    assert!((area - 3.).abs() < 1e-6);
+}
+
+#[test]
+fn vertex_colors_texture_coordinates_and_relative_indices_are_read() {
+    let shapes = Obj::load("tests/data/shapes.obj").unwrap();
+    let l = &shapes.group("L").unwrap().mesh;
+    assert_eq!(l.positions[2], [2., 1., 0.]);
+    assert_eq!(l.colors[2], [0., 1., 0.]);
+    assert_eq!(l.uvs[2], [1., 0.5]);
+
+    // Without any normals in the file they're computed from the faces.
+    let triangle = &shapes.group("triangle").unwrap().mesh;
+    assert_eq!(triangle.normals, [[0., 0., 1.]; 3]);
+    assert_eq!(triangle.colors, [[0.6, 0., 0.6]; 3]);
+    assert_eq!(triangle.indices, [0, 1, 2]);
+}
+
+#[test]
+fn mistakes_are_reported_with_their_line() {
+    let error = read("v 0 0 0\nv 1 0 0\n\nf 1 2 3\n").unwrap_err();
+    assert!(matches!(error, ObjError::Parse { line: 4, .. }), "{}", error);
+    let error = read("v 0 0 zero\n").unwrap_err();
+    assert!(matches!(error, ObjError::Parse { line: 1, .. }), "{}", error);
+    let error = read("v 0 0 0\nf 1 1\n").unwrap_err();
+    assert!(matches!(error, ObjError::Parse { line: 2, .. }), "{}", error);
+    assert_eq!(read("# nothing but a comment\n").unwrap(), Obj::default());
+}
+
+#[test]
+fn written_objects_read_back_the_same() {
+    let torus = Torus::new(1., 0.3).mesh().unwrap();
+    let mut bytes = Vec::new();
+    obj::write(&mut bytes, &Torus::new(1., 0.3)).unwrap();
+    let read_back = read(std::str::from_utf8(&bytes).unwrap()).unwrap();
+    assert_eq!(read_back.groups.len(), 1);
+    assert_eq!(corners(&read_back.groups[0].mesh), corners(&torus));
+}
+
+#[test]
+fn loaded_files_read_back_the_same() {
+    let cube = Obj::load("tests/data/cube.obj").unwrap();
+    let mut bytes = Vec::new();
+    cube.write(&mut bytes).unwrap();
+    let read_back = read(std::str::from_utf8(&bytes).unwrap()).unwrap();
+    assert_eq!(read_back.groups.len(), 2);
+    for (group, original) in read_back.groups.iter().zip(&cube.groups) {
+        assert_eq!(group.name, original.name);
+        // Material colors are written as vertex colors rather than as materials.
+        assert_eq!(group.material, None);
+        assert_eq!(corners(&group.mesh), corners(&original.mesh));
+    }
+}
+
+#[test]
+fn lines_cannot_be_written() {
+    let line = PolyLine::from_points(&[[0.; 3], [1.; 3]]);
+    assert!(matches!(obj::write(Vec::new(), &line), Err(ObjError::NoTriangles)));
+}
+
+#[test]
+fn groups_without_texture_coordinates_or_normals_keep_the_indices_of_later_groups() {
+    let plain = Mesh::new(vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]], vec![0, 1, 2]);
+    let torus = Torus::new(1., 0.3).mesh().unwrap();
+    let obj = Obj {
+        groups: vec![
+            ObjGroup { name: "plain".to_string(), material: None, mesh: plain.clone() },
+            ObjGroup { name: "torus".to_string(), material: None, mesh: torus.clone() },
+        ],
+    };
+    let mut bytes = Vec::new();
+    obj.write(&mut bytes).unwrap();
+    let read_back = read(std::str::from_utf8(&bytes).unwrap()).unwrap();
+    // Normals are made up from the faces where the file has none.
+    let without_normals = |mesh: &Mesh| corners(mesh).into_iter().map(|(p, c, _, uv)| (p, c, uv)).collect::<Vec<_>>();
+    assert_eq!(without_normals(&read_back.group("plain").unwrap().mesh), without_normals(&plain));
+    // The torus comes after a group without any, yet keeps its own texture coordinates and normals.
+    assert_eq!(corners(&read_back.group("torus").unwrap().mesh), corners(&torus));
+}
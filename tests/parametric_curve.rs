@@ -0,0 +1,97 @@
+use std::f32::consts::PI;
+
+use draw3d::geometry::{Domain, ParametricCurve};
+use draw3d::vertex::Vertex;
+
+fn circle(samples: usize) -> ParametricCurve {
+    ParametricCurve::new(|t| [2. * t.cos(), 2. * t.sin(), 0.], Domain::linspace(0., 2. * PI, samples).periodic())
+}
+
+fn signed_volume(vertices: &[Vertex], indices: &[u32]) -> f32 {
+    indices.chunks_exact(3).map(|t| {
+        let [a, b, c] = [t[0], t[1], t[2]].map(|i| vertices[i as usize].position);
+        (a[0] * (b[1] * c[2] - b[2] * c[1]) + a[1] * (b[2] * c[0] - b[0] * c[2]) + a[2] * (b[0] * c[1] - b[1] * c[0])) / 6.
+    }).sum()
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[test]
+fn closed_curve_line_wraps_around() {
+    let (vertices, indices) = circle(8).tessellate();
+    assert_eq!(vertices.len(), 8);
+    assert_eq!(indices.len(), 16);
+    assert_eq!(indices[14..], [7, 0]);
+
+    // A duplicated endpoint is dropped rather than drawn twice.
+    let duplicated = ParametricCurve::new(|t| [t.cos(), t.sin(), 0.], Domain::linspace(0., 2. * PI, 9).periodic().endpoint(true));
+    assert_eq!(duplicated.tessellate().0.len(), 8);
+}
+
+#[test]
+fn open_tube_encloses_a_capped_cylinder() {
+    let tube = ParametricCurve::new(|t| [0., 0., t], Domain::linspace(0., 3., 4)).tube(0.5).sides(256);
+    let (vertices, indices) = tube.tessellate();
+    let volume = signed_volume(&vertices, &indices);
+    assert!((volume - PI * 0.25 * 3.).abs() < 0.01, "volume {volume}");
+}
+
+#[test]
+fn closed_tube_is_a_torus() {
+    let (vertices, indices) = circle(256).tube(0.5).sides(64).tessellate();
+    let volume = signed_volume(&vertices, &indices);
+    let expected = 2. * PI * PI * 2. * 0.25;
+    assert!((volume - expected).abs() < 0.01 * expected, "volume {volume} != {expected}");
+    for v in vertices.iter() {
+        let ring = [v.position[0], v.position[1], 0.];
+        let r = (dot(ring, ring)).sqrt();
+        let center = [ring[0] * 2. / r, ring[1] * 2. / r, 0.];
+        let offset = [0, 1, 2].map(|k| v.position[k] - center[k]);
+        assert!((dot(offset, offset).sqrt() - 0.5).abs() < 1e-3);
+        assert!(dot(offset, v.normal) > 0.49);
+    }
+}
+
+#[test]
+fn frames_are_orthonormal_and_untwisted() {
+    let helix = ParametricCurve::new(|t| [t.cos(), t.sin(), t / 4.], Domain::linspace(0., 4. * PI, 400));
+    let frames = helix.frames();
+    for f in frames.iter() {
+        for (a, b) in [(f.tangent, f.normal), (f.normal, f.binormal), (f.binormal, f.tangent)] {
+            assert!(dot(a, b).abs() < 1e-4);
+        }
+        for v in [f.tangent, f.normal, f.binormal] {
+            assert!((dot(v, v) - 1.).abs() < 1e-4);
+        }
+    }
+    // Parallel transport has no rotation about the tangent between samples.
+    for pair in frames.windows(2) {
+        assert!(dot(pair[0].normal, pair[1].tangent).abs() + dot(pair[1].normal, pair[0].tangent).abs() < 0.1);
+        assert!((dot(pair[0].binormal, pair[1].normal) - dot(pair[1].binormal, pair[0].normal)).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn closed_frames_meet_up() {
+    // A trefoil knot, whose transported frame comes back twisted.
+    let trefoil = ParametricCurve::new(
+        |t| [t.sin() + 2. * (2. * t).sin(), t.cos() - 2. * (2. * t).cos(), -(3. * t).sin()],
+        Domain::linspace(0., 2. * PI, 300).periodic(),
+    );
+    let frames = trefoil.frames();
+    let (first, last) = (frames[0], frames[frames.len() - 1]);
+    assert!(dot(first.normal, last.normal) > 0.99);
+}
+
+#[test]
+fn ribbon_is_two_sided() {
+    let ribbon = ParametricCurve::new(|t| [t, 0., 0.], Domain::linspace(0., 1., 5)).ribbon(0.2);
+    let (vertices, indices) = ribbon.tessellate();
+    assert_eq!(vertices.len(), 2 * 2 * 5);
+    assert_eq!(indices.len(), 2 * 4 * 6);
+    assert!(signed_volume(&vertices, &indices).abs() < 1e-6);
+    let width: f32 = vertices[1].position.iter().zip(vertices[0].position.iter()).map(|(a, b)| (a - b).powi(2)).sum::<f32>().sqrt();
+    assert!((width - 0.2).abs() < 1e-5);
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use draw3d::cgmath::{Matrix4, Point3, Vector3};
+use draw3d::geometry::{Adaptive, Boundary, Domain, ParametricSurface};
+use draw3d::vertex::Vertex;
+
+fn sphere(samples: usize) -> ParametricSurface {
+    ParametricSurface::new(
+        |phi: f32, theta: f32| [theta.cos() * phi.sin(), theta.sin() * phi.sin(), phi.cos()],
+        Domain::linspace(0., PI, samples + 1).degenerate(),
+        Domain::linspace(0., 2. * PI, 2 * samples).periodic(),
+    )
+}
+
+fn torus(theta: Vec<f32>, phi: Vec<f32>) -> ParametricSurface {
+    ParametricSurface::new(
+        |theta: f32, phi: f32| {
+            let r = 1. + 0.3 * phi.cos();
+            [r * theta.cos(), r * theta.sin(), 0.3 * phi.sin()]
+        },
+        theta,
+        phi,
+    )
+    .boundaries(Boundary::Periodic, Boundary::Periodic)
+}
+
+/// Counts how often each directed edge is used by the triangles.
+fn directed_edges(indices: &[u32]) -> HashMap<(u32, u32), usize> {
+    let mut edges = HashMap::new();
+    for t in indices.chunks_exact(3) {
+        for (a, b) in [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])] {
+            *edges.entry((a, b)).or_insert(0) += 1;
+        }
+    }
+    edges
+}
+
+/// Every edge is shared by exactly two triangles that traverse it in opposite directions.
+fn assert_watertight(indices: &[u32]) {
+    let edges = directed_edges(indices);
+    for (&(a, b), &count) in edges.iter() {
+        assert_eq!(count, 1, "edge {a}->{b} is used {count} times in the same direction");
+        assert!(edges.contains_key(&(b, a)), "edge {a}->{b} has no opposite edge");
+    }
+}
+
+fn euler_characteristic(vertices: &[Vertex], indices: &[u32]) -> i64 {
+    let directed = directed_edges(indices);
+    let edges = directed
+        .keys()
+        .filter(|&&(a, b)| a < b || !directed.contains_key(&(b, a)))
+        .count();
+    let faces = indices.len() / 3;
+    vertices.len() as i64 - edges as i64 + faces as i64
+}
+
+#[test]
+fn sphere_is_watertight_with_welded_poles() {
+    let (vertices, indices) = sphere(8).tessellate(0.);
+
+    assert_watertight(&indices);
+    assert_eq!(euler_characteristic(&vertices, &indices), 2);
+    // 7 interior rings of 16 samples plus the two poles.
+    assert_eq!(vertices.len(), 7 * 16 + 2);
+}
+
+#[test]
+fn sphere_normals_and_winding_point_outwards() {
+    let (vertices, indices) = sphere(8).tessellate(0.);
+
+    for v in vertices.iter() {
+        let dot: f32 = v.normal.iter().zip(v.position.iter()).map(|(n, p)| n * p).sum();
+        assert!(dot > 0.9, "normal {:?} at {:?} doesn't point outwards", v.normal, v.position);
+    }
+    for t in indices.chunks_exact(3) {
+        let [a, b, c] = [t[0], t[1], t[2]].map(|i| vertices[i as usize].position);
+        let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+        let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+        let n = [ab[1] * ac[2] - ab[2] * ac[1], ab[2] * ac[0] - ab[0] * ac[2], ab[0] * ac[1] - ab[1] * ac[0]];
+        assert!(n[0] * a[0] + n[1] * a[1] + n[2] * a[2] > 0.);
+    }
+}
+
+#[test]
+fn torus_is_watertight() {
+    let samples = |n: usize| Domain::linspace(0., 2. * PI, n).periodic().values();
+    let (vertices, indices) = torus(samples(24), samples(12)).tessellate(0.);
+
+    assert_watertight(&indices);
+    assert_eq!(euler_characteristic(&vertices, &indices), 0);
+    assert_eq!(vertices.len(), 24 * 12);
+}
+
+#[test]
+fn duplicated_periodic_endpoint_is_welded() {
+    let samples = |n: usize| Domain::linspace(0., 2. * PI, n + 1).values();
+    let (vertices, indices) = torus(samples(24), samples(12)).tessellate(0.);
+
+    assert_watertight(&indices);
+    assert_eq!(vertices.len(), 24 * 12);
+}
+
+#[test]
+fn open_surface_has_a_single_boundary_loop() {
+    let range = Domain::arange(0., 5., 1.);
+    let plane = ParametricSurface::new(|x, y| [x, y, 0.], range.clone(), range);
+    let (vertices, indices) = plane.tessellate(0.);
+
+    let edges = directed_edges(&indices);
+    let boundary = edges.keys().filter(|&&(a, b)| !edges.contains_key(&(b, a))).count();
+    assert_eq!(vertices.len(), 25);
+    assert_eq!(indices.len(), 4 * 4 * 6);
+    assert_eq!(boundary, 4 * 4);
+    assert_eq!(euler_characteristic(&vertices, &indices), 1);
+}
+
+#[test]
+fn boundaries_override_the_domains() {
+    let open = Domain::linspace(0., 2. * PI, 8).endpoint(false);
+    let (_, indices) = torus(open.values(), open.values()).tessellate(0.);
+    let (_, overridden) = ParametricSurface::new(|a: f32, b: f32| [a, b, 0.], open.clone().periodic(), open)
+        .boundaries(Boundary::Open, Boundary::Open)
+        .tessellate(0.);
+
+    assert_eq!(indices.len(), 8 * 8 * 6);
+    assert_eq!(overridden.len(), 7 * 7 * 6);
+}
+
+#[test]
+fn adaptive_sphere_is_refined_without_cracks() {
+    let (uniform, _) = sphere(4).tessellate(0.);
+    let (vertices, indices) = sphere(4).adaptive(Adaptive::world(0.01)).tessellate(0.);
+
+    assert!(vertices.len() > 4 * uniform.len());
+    assert_watertight(&indices);
+    assert_eq!(euler_characteristic(&vertices, &indices), 2);
+}
+
+#[test]
+fn adaptive_refinement_follows_curvature() {
+    // The outside of the torus bends less than the inside, so neighbouring cells end up
+    // refined to different depths and the T-junctions between them have to be stitched.
+    let samples = |n: usize| Domain::linspace(0., 2. * PI, n).periodic().values();
+    let surface = torus(samples(6), samples(6)).adaptive(Adaptive::world(0.02).max_depth(3));
+    let (vertices, indices) = surface.tessellate(0.);
+
+    assert!(vertices.len() > 36 && vertices.len() < 36 * 64);
+    assert_watertight(&indices);
+    assert_eq!(euler_characteristic(&vertices, &indices), 0);
+}
+
+#[test]
+fn adaptive_plane_is_not_refined() {
+    let range = Domain::linspace(0., 1., 5);
+    let plane = ParametricSurface::new(|x, y| [x, y, x * y], range.clone(), range);
+    let (uniform, _) = plane.tessellate(0.);
+    let (vertices, _) = plane.adaptive(Adaptive::world(1e-4)).tessellate(0.);
+
+    assert_eq!(vertices.len(), uniform.len());
+}
+
+#[test]
+fn screen_tolerance_refines_less_further_away() {
+    let view_proj = |distance: f32| {
+        let view = Matrix4::look_at_rh(Point3::new(0., -distance, 0.), Point3::new(0., 0., 0.), Vector3::unit_z());
+        draw3d::cgmath::perspective(draw3d::cgmath::Deg(45.), 1., 0.1, 100.) * view
+    };
+    let triangles = |distance: f32| {
+        let adaptive = Adaptive::screen(view_proj(distance), [800., 800.], 0.5);
+        sphere(4).adaptive(adaptive).tessellate(0.).1.len() / 3
+    };
+
+    assert!(triangles(3.) > triangles(30.));
+}
+
+#[test]
+fn adaptive_depth_is_clamped() {
+    assert_eq!(Adaptive::world(0.1).max_depth(200).max_depth, draw3d::geometry::adaptive::MAX_DEPTH);
+    // Even set directly past the limit, a flat surface tessellates without overflowing.
+    let range = Domain::linspace(0., 1., 3);
+    let plane = ParametricSurface::new(|x, y| [x, y, 0.], range.clone(), range);
+    let adaptive = Adaptive { max_depth: 64, ..Adaptive::world(0.1) };
+    let (vertices, _) = plane.adaptive(adaptive).tessellate(0.);
+    assert_eq!(vertices.len(), 9);
+}
+
+#[test]
+fn a_range_without_span_has_finite_texture_coordinates() {
+    // Every u sample is the same, as for a surface collapsed to a curve.
+    let surface = ParametricSurface::new(|x, y| [x, y, 0.], Domain::linspace(1., 1., 3), Domain::linspace(0., 1., 3));
+    let (vertices, _) = surface.tessellate(0.);
+    assert!(vertices.iter().all(|v| v.uv[0] == 0. && (0. ..=1.).contains(&v.uv[1])));
+}
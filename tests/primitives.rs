@@ -0,0 +1,65 @@
+use std::f32::consts::PI;
+
+use draw3d::geometry::primitives::{Arrow, Capsule, Cone, Cuboid, Cylinder, Disk, Icosphere, Plane, Torus, UvSphere};
+use draw3d::vertex::Vertex;
+
+/// The volume enclosed by the triangles, positive when they face outwards.
+fn signed_volume(vertices: &[Vertex], indices: &[u32]) -> f32 {
+    indices.chunks_exact(3).map(|t| {
+        let [a, b, c] = [t[0], t[1], t[2]].map(|i| vertices[i as usize].position);
+        (a[0] * (b[1] * c[2] - b[2] * c[1]) + a[1] * (b[2] * c[0] - b[0] * c[2]) + a[2] * (b[0] * c[1] - b[1] * c[0])) / 6.
+    }).sum()
+}
+
+fn assert_volume((vertices, indices): (Vec<Vertex>, Vec<u32>), expected: f32) {
+    let volume = signed_volume(&vertices, &indices);
+    assert!((volume - expected).abs() < 0.01 * expected, "volume {volume} != {expected}");
+    for v in vertices.iter() {
+        let length = v.normal.iter().map(|n| n * n).sum::<f32>().sqrt();
+        assert!((length - 1.).abs() < 1e-4, "normal {:?} isn't unit length", v.normal);
+        assert!(v.uv.iter().all(|uv| (0. ..=1.).contains(uv)), "uv {:?} out of range", v.uv);
+    }
+}
+
+#[test]
+fn closed_primitives_enclose_their_volume_facing_outwards() {
+    let sphere = 4. / 3. * PI;
+    assert_volume(UvSphere::new(1.).segments(128).rings(64).tessellate(), sphere);
+    assert_volume(Icosphere::new(1.).subdivisions(4).tessellate(), sphere);
+    assert_volume(Cuboid::new([1., 2., 3.]).tessellate(), 6.);
+    assert_volume(Cylinder::new(1., 2.).segments(256).tessellate(), 2. * PI);
+    assert_volume(Cone::new(1., 3.).segments(256).tessellate(), PI);
+    assert_volume(Capsule::new(1., 2.).segments(128).rings(32).tessellate(), 2. * PI + sphere);
+    assert_volume(Torus::new(2., 0.5).segments(128).sides(64).tessellate(), 2. * PI * PI * 2. * 0.25);
+}
+
+#[test]
+fn arrow_points_from_start_to_end() {
+    let (vertices, indices) = Arrow::new([1., 1., 1.], [1., 1., -1.]).tessellate();
+    let tip = vertices.iter().map(|v| v.position[2]).fold(f32::MAX, f32::min);
+    let base = vertices.iter().map(|v| v.position[2]).fold(f32::MIN, f32::max);
+
+    assert!((tip + 1.).abs() < 1e-5 && (base - 1.).abs() < 1e-5);
+    assert!(signed_volume(&vertices, &indices) > 0.);
+}
+
+#[test]
+fn flat_primitives_face_up() {
+    let shapes = [
+        (Disk::new(1.).segments(256).tessellate(), PI),
+        (Plane::new(2., 3.).subdivisions(4, 5).tessellate(), 6.),
+    ];
+    for ((vertices, indices), expected) in shapes {
+        // Area seen from above, which is negative for triangles facing down.
+        let area: f32 = indices.chunks_exact(3).map(|t| {
+            let [a, b, c] = [t[0], t[1], t[2]].map(|i| vertices[i as usize].position);
+            ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) / 2.
+        }).sum();
+        assert!((area - expected).abs() < 0.01 * expected, "area {area} != {expected}");
+        assert!(vertices.iter().all(|v| v.normal == [0., 0., 1.]));
+    }
+
+    let (vertices, indices) = Plane::new(2., 3.).subdivisions(4, 5).tessellate();
+    assert_eq!(vertices.len(), 5 * 6);
+    assert_eq!(indices.len(), 4 * 5 * 6);
+}
@@ -0,0 +1,126 @@
+use draw3d::cgmath::{Matrix4, Quaternion, Rad, Rotation3, SquareMatrix, Vector3, Vector4};
+use draw3d::geometry::{Domain, Draw, ParametricSurface};
+use draw3d::scene::Scene;
+use draw3d::transform::Transform;
+
+fn origin(matrix: Matrix4<f32>) -> Vector3<f32> {
+    (matrix * Vector4::new(0., 0., 0., 1.)).truncate()
+}
+
+fn close(a: Vector3<f32>, b: Vector3<f32>) -> bool {
+    (a - b).x.abs() < 1e-5 && (a - b).y.abs() < 1e-5 && (a - b).z.abs() < 1e-5
+}
+
+#[test]
+fn children_move_with_their_parents() {
+    let mut scene = Scene::new();
+    let arm = scene.add_group();
+    let hand = scene.add_group();
+    scene.set_parent(hand, Some(arm));
+    scene.transform_mut(hand).translate((1., 0., 0.));
+    assert!(close(origin(scene.world_matrix(hand)), Vector3::new(1., 0., 0.)));
+
+    // Moving the parent after the child's world matrix was computed still moves the child.
+    scene.transform_mut(arm).rotate(Quaternion::from_angle_z(Rad(std::f32::consts::FRAC_PI_2)));
+    assert!(close(origin(scene.world_matrix(hand)), Vector3::new(0., 1., 0.)));
+
+    let mut lifted = *scene.transform(arm);
+    lifted.translate((0., 0., 2.));
+    scene.set_transform(arm, lifted);
+    assert!(close(origin(scene.world_matrix(hand)), Vector3::new(0., 1., 2.)));
+    assert_eq!(scene.world_matrix(arm), lifted.matrix());
+}
+
+#[test]
+fn reparenting_keeps_the_local_transform() {
+    let mut scene = Scene::new();
+    let a = scene.add_group();
+    let b = scene.add_group();
+    let child = scene.add_group();
+    scene.transform_mut(a).translate((1., 0., 0.));
+    scene.transform_mut(b).translate((0., 5., 0.));
+    scene.transform_mut(child).translate((0., 0., 1.));
+
+    scene.set_parent(child, Some(a));
+    assert!(close(origin(scene.world_matrix(child)), Vector3::new(1., 0., 1.)));
+    scene.set_parent(child, Some(b));
+    assert!(close(origin(scene.world_matrix(child)), Vector3::new(0., 5., 1.)));
+    assert_eq!(scene.children(a), []);
+    assert_eq!(scene.children(b), [child]);
+    assert_eq!(scene.parent(child), Some(b));
+    assert_eq!(scene.roots(), [a, b]);
+
+    scene.set_parent(child, None);
+    assert_eq!(scene.world_matrix(child), Transform::new().translate((0., 0., 1.)).matrix());
+    assert_eq!(scene.roots(), [a, b, child]);
+}
+
+#[test]
+#[should_panic(expected = "below itself")]
+fn cycles_are_rejected() {
+    let mut scene = Scene::new();
+    let a = scene.add_group();
+    let b = scene.add_group();
+    scene.set_parent(b, Some(a));
+    scene.set_parent(a, Some(b));
+}
+
+#[test]
+fn removing_a_node_removes_its_subtree() {
+    let mut scene = Scene::new();
+    let a = scene.add_group();
+    let b = scene.add_group();
+    let c = scene.add_group();
+    let other = scene.add_group();
+    scene.set_parent(b, Some(a));
+    scene.set_parent(c, Some(b));
+    assert_eq!(scene.len(), 4);
+
+    assert!(scene.remove(a));
+    assert_eq!(scene.len(), 1);
+    assert!(!scene.contains(a) && !scene.contains(b) && !scene.contains(c));
+    assert!(scene.contains(other));
+    assert!(!scene.remove(b));
+    assert_eq!(scene.roots(), [other]);
+
+    // New nodes reuse the slots but not the handles.
+    let d = scene.add_group();
+    assert!(scene.contains(d));
+    assert!(!scene.contains(a) && !scene.contains(b) && !scene.contains(c));
+    assert_eq!(scene.world_matrix(d), Matrix4::identity());
+}
+
+#[test]
+fn nodes_start_visible_without_geometry() {
+    let mut scene = Scene::new();
+    let group = scene.add_group();
+    assert!(scene.is_visible(group));
+    assert!(scene.geometry(group).is_none());
+    scene.set_visible(group, false);
+    assert!(!scene.is_visible(group));
+}
+
+#[test]
+fn children_are_added_straight_below_their_parent() {
+    let mut scene = Scene::new();
+    let root = scene.add_group();
+    let mut parent = root;
+    for _ in 0..100 {
+        let child = scene.add_child_group(parent);
+        scene.transform_mut(child).translate((1., 0., 0.));
+        parent = child;
+    }
+    assert_eq!(scene.roots(), [root]);
+    assert_eq!(scene.len(), 101);
+    assert_eq!(scene.children(root).len(), 1);
+    assert!(close(origin(scene.world_matrix(parent)), Vector3::new(100., 0., 0.)));
+}
+
+#[test]
+fn animated_surfaces_say_so() {
+    // Scenes upload surfaces once, so they need telling apart from static ones.
+    let animated = ParametricSurface::animated(|a: f32, b: f32, t: f32| [a, b, t], Domain::linspace(0., 1., 3), Domain::linspace(0., 1., 3));
+    let still = ParametricSurface::new(|a: f32, b: f32| [a, b, 0.], Domain::linspace(0., 1., 3), Domain::linspace(0., 1., 3));
+    assert!(Draw::is_animated(&animated));
+    assert!(!Draw::is_animated(&still));
+}
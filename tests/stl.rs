@@ -0,0 +1,113 @@
+use std::f32::consts::PI;
+
+use draw3d::geometry::primitives::{Cuboid, Plane, Torus};
+use draw3d::geometry::stl::{self, StlError, StlFormat};
+use draw3d::geometry::{Domain, Draw, Mesh, MeshError, ParametricSurface, PolyLine};
+
+mod common;
+use common::signed_volume;
+
+fn sphere() -> ParametricSurface {
+    ParametricSurface::new(
+        |phi: f32, theta: f32| [theta.cos() * phi.sin(), theta.sin() * phi.sin(), phi.cos()],
+        Domain::linspace(0., PI, 17).degenerate(),
+        Domain::linspace(0., 2. * PI, 32).periodic(),
+    )
+}
+
+fn volume(mesh: &Mesh) -> f32 {
+    signed_volume(&mesh.positions, &mesh.indices)
+}
+
+fn round_trip(object: &dyn Draw, format: StlFormat) -> Mesh {
+    let mut bytes = Vec::new();
+    stl::write(&mut bytes, object, format).unwrap();
+    stl::read(bytes.as_slice()).unwrap()
+}
+
+#[test]
+fn both_formats_read_the_same_triangles() {
+    let ascii = stl::load("tests/data/tetrahedron.stl").unwrap();
+    // The binary file's header starts with "solid", like an ASCII file.
+    let binary = stl::load("tests/data/tetrahedron_binary.stl").unwrap();
+    assert_eq!(ascii, binary);
+    assert_eq!(ascii.triangle_count(), 4);
+    assert_eq!(ascii.positions.len(), 12);
+
+    // Normals come from the winding, even where the file left them out.
+    let slanted = 1. / 3f32.sqrt();
+    assert_eq!(ascii.normals[0], [0., 0., -1.]);
+    assert!(ascii.normals[9..].iter().all(|n| n.iter().all(|x| (x - slanted).abs() < 1e-6)));
+
+    let mut welded = ascii.clone();
+    welded.weld(1e-6);
+    assert_eq!(welded.positions.len(), 4);
+    assert!(welded.is_closed());
+    assert!((volume(&welded) - 1. / 6.).abs() < 1e-6);
+}
+
+#[test]
+fn written_surfaces_read_back_the_same() {
+    for object in [&sphere() as &dyn Draw, &Torus::new(1., 0.3), &Cuboid::new([1., 2., 3.])] {
+        let ascii = round_trip(object, StlFormat::Ascii);
+        let binary = round_trip(object, StlFormat::Binary);
+        assert_eq!(ascii, binary);
+
+        let mut welded = binary.clone();
+        welded.weld(1e-6);
+        assert_eq!(welded.validate_closed(), Ok(()));
+        let mut original = object.mesh().unwrap();
+        original.weld(1e-6);
+        assert_eq!(binary.triangle_count(), original.triangle_count());
+        assert!((volume(&binary) - volume(&original)).abs() < 1e-5);
+    }
+    let cuboid = round_trip(&Cuboid::new([1., 2., 3.]), StlFormat::Binary);
+    assert!((volume(&cuboid) - 6.).abs() < 1e-5);
+}
+
+#[test]
+fn open_surfaces_are_not_written() {
+    let mut bytes = Vec::new();
+    match stl::write(&mut bytes, &Plane::new(1., 1.), StlFormat::Binary) {
+        Err(StlError::NotClosed(errors)) => {
+            assert_eq!(errors.len(), 4);
+            assert!(errors.iter().all(|e| matches!(e, MeshError::BoundaryEdge { .. })));
+        }
+        other => panic!("expected the plane to be refused, got {:?}", other),
+    }
+    assert!(bytes.is_empty());
+
+    // Writing the mesh itself skips the check.
+    stl::write_mesh(&mut bytes, &Plane::new(1., 1.).mesh().unwrap(), StlFormat::Binary).unwrap();
+    assert_eq!(stl::read(bytes.as_slice()).unwrap().triangle_count(), 2);
+
+    let line = PolyLine::from_points(&[[0.; 3], [1.; 3]]);
+    assert!(matches!(stl::write(Vec::new(), &line, StlFormat::Ascii), Err(StlError::NoTriangles)));
+}
+
+#[test]
+fn invalid_or_collapsed_meshes_are_refused_without_panicking() {
+    let mut mesh = Plane::new(1., 1.).mesh().unwrap();
+    mesh.indices[4] = 99;
+    match stl::write_mesh(Vec::new(), &mesh, StlFormat::Ascii) {
+        Err(StlError::InvalidMesh(errors)) => assert_eq!(errors, [MeshError::IndexOutOfRange { triangle: 1, index: 99 }]),
+        other => panic!("expected the mesh to be refused, got {:?}", other),
+    }
+
+    // Every vertex in the same place, away from the origin, so welding collapses every triangle.
+    let mut collapsed = Cuboid::cube(1.).mesh().unwrap();
+    collapsed.positions.iter_mut().for_each(|p| *p = [5., 5., 5.]);
+    assert!(matches!(stl::write(Vec::new(), &collapsed, StlFormat::Binary), Err(StlError::NoTriangles)));
+}
+
+#[test]
+fn broken_files_are_reported() {
+    let error = stl::read("solid x\n  facet normal 0 0 1\n    outer loop\n      vertex 0 0\n".as_bytes()).unwrap_err();
+    assert!(matches!(error, StlError::Parse { line: 4, .. }), "{}", error);
+    let error = stl::read("solid x\nfacet\nvertex 0 0 0\nvertex 1 0 0\nendfacet\n".as_bytes()).unwrap_err();
+    assert!(matches!(error, StlError::Parse { line: 5, .. }), "{}", error);
+
+    let mut bytes = std::fs::read("tests/data/tetrahedron_binary.stl").unwrap();
+    bytes.truncate(bytes.len() - 10);
+    assert!(matches!(stl::read(bytes.as_slice()), Err(StlError::Truncated { triangles: 4, .. })));
+}
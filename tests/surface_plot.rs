@@ -0,0 +1,88 @@
+use draw3d::colormap::{self, Colormap};
+use draw3d::geometry::{Colorbar, Coloring, Domain, SurfacePlot};
+
+fn assert_color(actual: [f32; 3], expected: [f32; 3]) {
+    assert!((0..3).all(|k| (actual[k] - expected[k]).abs() < 1e-4), "{actual:?} != {expected:?}");
+}
+
+#[test]
+fn colormap_interpolates_between_stops() {
+    let colormap = Colormap::new(vec![(1., [1., 1., 1.]), (0., [0., 0., 0.]), (0.5, [1., 0., 0.])]);
+
+    assert_color(colormap.map(-1.), [0., 0., 0.]);
+    assert_color(colormap.map(0.5), [1., 0., 0.]);
+    assert_color(colormap.map(2.), [1., 1., 1.]);
+    // Halfway between sRGB 0 and 1 is sRGB 0.5, which is about 0.214 linear.
+    assert_color(Colormap::grayscale().map(0.5), [0.21404; 3]);
+    assert_color(colormap.map_range(15., 10., 20.), [1., 0., 0.]);
+}
+
+#[test]
+fn plot_is_colored_by_height() {
+    let range = Domain::linspace(-1., 1., 11);
+    let colormap = Colormap::coolwarm();
+    let plot = SurfacePlot::new(|x, _| x, range.clone(), range).colormap(colormap.clone());
+    let (vertices, indices) = plot.tessellate();
+
+    assert_eq!(plot.value_range(), [-1., 1.]);
+    assert_eq!(vertices.len(), 121);
+    assert_eq!(indices.len(), 10 * 10 * 6);
+    for v in vertices.iter() {
+        assert_color(v.color, colormap.map_range(v.position[0], -1., 1.));
+        assert!(v.normal[2] > 0.);
+    }
+}
+
+#[test]
+fn grid_matches_function() {
+    let f = |x: f32, y: f32| x * y;
+    let values = (0..5 * 4).map(|k| f((k % 5) as f32 / 4., (k / 5) as f32 / 3.)).collect();
+    let (grid, _) = SurfacePlot::from_grid(values, [5, 4], [0., 1.], [0., 1.]).tessellate();
+    let (function, _) = SurfacePlot::new(f, Domain::linspace(0., 1., 5), Domain::linspace(0., 1., 4)).tessellate();
+
+    for (a, b) in grid.iter().zip(function.iter()) {
+        assert_color(a.position, b.position);
+        assert_color(a.normal, b.normal);
+        assert_color(a.color, b.color);
+    }
+}
+
+#[test]
+fn custom_scalars_and_holes() {
+    let range = Domain::linspace(0., 1., 3);
+    let plot = SurfacePlot::new(|x, y| if x > 0.9 && y > 0.9 { f32::NAN } else { 0. }, range.clone(), range)
+        .color_values((0..9).map(|k| k as f32).collect())
+        .range(0., 8.);
+    let (vertices, indices) = plot.tessellate();
+
+    // The corner cell touching the missing sample is left out.
+    assert_eq!(indices.len(), 3 * 6);
+    assert_color(vertices[8].color, Colormap::viridis().map(1.));
+}
+
+#[test]
+fn plots_color_through_a_coloring() {
+    assert_eq!(colormap::finite_range(&[2., f32::NAN, -1., f32::INFINITY]), [-1., 2.]);
+    // Without any finite scalar there's nothing to span, so the range falls back to 0 to 1.
+    assert_eq!(colormap::finite_range(&[f32::NAN, f32::NEG_INFINITY]), [0., 1.]);
+
+    let range = Domain::linspace(0., 1., 3);
+    let plot = SurfacePlot::new(|_, _| 0., range.clone(), range.clone()).color_by(|_| f32::NAN);
+    assert_eq!(plot.value_range(), [0., 1.]);
+
+    // A coloring gets x and y as its parameters.
+    let colormap = Colormap::plasma();
+    let plot = SurfacePlot::new(|_, _| 0., range.clone(), range)
+        .coloring(Coloring::by_scalar(|x, y, _| x + y, colormap.clone()));
+    assert_eq!(plot.value_range(), [0., 2.]);
+    let (bar, _) = plot.colorbar().tessellate();
+    let (expected, _) = Colorbar::new(colormap).tessellate();
+    assert!(bar.iter().zip(&expected).all(|(a, b)| a.color == b.color));
+}
+
+#[test]
+#[should_panic(expected = "need one color value per sample")]
+fn color_values_must_match_the_samples() {
+    let range = Domain::linspace(0., 1., 3);
+    let _ = SurfacePlot::new(|_, _| 0., range.clone(), range).color_values(vec![0.; 8]);
+}
@@ -0,0 +1,57 @@
+use draw3d::time::{Clock, MAX_UPDATES_PER_FRAME};
+
+const STEP: f32 = 0.25;
+
+#[test]
+fn slow_frames_catch_up_with_several_steps() {
+    let mut clock = Clock::new();
+    assert_eq!(clock.fixed_steps(0.1, STEP), 0);
+    assert_eq!(clock.fixed_steps(0.1, STEP), 0);
+    // 0.3 seconds have now passed, enough for one step.
+    assert_eq!(clock.fixed_steps(0.1, STEP), 1);
+    // One frame a second late runs all the steps it missed.
+    assert_eq!(clock.fixed_steps(1., STEP), 4);
+}
+
+#[test]
+fn the_remainder_carries_over_to_the_next_frame() {
+    let mut clock = Clock::new();
+    assert_eq!(clock.fixed_steps(0.375, STEP), 1);
+    // The 0.125 left over plus this frame's 0.125 make another step.
+    assert_eq!(clock.fixed_steps(0.125, STEP), 1);
+    assert_eq!(clock.fixed_steps(0.125, STEP), 0);
+    assert_eq!(clock.fixed_steps(0.125, STEP), 1);
+}
+
+#[test]
+fn frames_too_far_behind_drop_the_time_they_cannot_catch_up() {
+    let mut clock = Clock::new();
+    assert_eq!(clock.fixed_steps(100., STEP), MAX_UPDATES_PER_FRAME);
+    // Nothing is left over from the long frame.
+    assert_eq!(clock.fixed_steps(0.125, STEP), 0);
+    assert_eq!(clock.fixed_steps(0.125, STEP), 1);
+
+    // Exactly the most steps allowed still keeps the remainder.
+    let mut clock = Clock::new();
+    assert_eq!(clock.fixed_steps(MAX_UPDATES_PER_FRAME as f32 * STEP + 0.125, STEP), MAX_UPDATES_PER_FRAME);
+    assert_eq!(clock.fixed_steps(0.125, STEP), 1);
+}
+
+#[test]
+fn advancing_moves_simulated_time() {
+    let mut clock = Clock::new();
+    for _ in 0..clock.fixed_steps(1., STEP) {
+        clock.advance(STEP);
+    }
+    assert_eq!(clock.time(), 1.);
+    assert_eq!(clock.delta(), STEP);
+    clock.end_frame();
+    assert_eq!(clock.frame_count(), 1);
+}
+
+#[test]
+#[should_panic(expected = "finite and positive")]
+fn fixed_update_rates_must_be_positive() {
+    fn model(_app: &draw3d::app::App) {}
+    let _ = draw3d::app::AppBuilder::app(model).fixed_update_rate(0.);
+}
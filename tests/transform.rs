@@ -0,0 +1,39 @@
+use std::f32::consts::FRAC_PI_2;
+
+use draw3d::cgmath::{Matrix4, MetricSpace, Point3, Quaternion, Rad, Rotation3, Transform as _, Vector3};
+use draw3d::transform::Transform;
+
+fn close(a: Point3<f32>, b: [f32; 3]) -> bool {
+    a.distance(Point3::from(b)) < 1e-5
+}
+
+fn quarter_turn() -> Quaternion<f32> {
+    Quaternion::from_angle_z(Rad(FRAC_PI_2))
+}
+
+#[test]
+fn scale_then_rotate_then_translate() {
+    let mut transform = Transform::new();
+    transform.translate([1., 2., 3.]).rotate(quarter_turn()).scale([2., 1., 1.]);
+    // (1, 0, 0) is stretched to (2, 0, 0), turned to (0, 2, 0), then moved.
+    assert!(close(transform.matrix().transform_point(Point3::new(1., 0., 0.)), [1., 4., 3.]));
+
+    // The order of the builder calls doesn't matter.
+    let mut reordered = Transform::new();
+    reordered.scale([2., 1., 1.]).rotate(quarter_turn()).translate([1., 2., 3.]);
+    assert_eq!(transform.matrix(), reordered.matrix());
+}
+
+#[test]
+fn repeated_calls_accumulate() {
+    let mut transform = Transform::new();
+    transform.translate([1., 0., 0.]).translate([0., 1., 0.]);
+    transform.scale([2., 2., 2.]).scale([1., 3., 1.]);
+    transform.rotate(quarter_turn()).rotate(quarter_turn());
+    assert_eq!(transform.translation, Vector3::new(1., 1., 0.));
+    assert_eq!(transform.scale, Vector3::new(2., 6., 2.));
+    // Two quarter turns make a half turn.
+    assert!(close(transform.matrix().transform_point(Point3::new(1., 0., 0.)), [-1., 1., 0.]));
+
+    assert_eq!(Transform::default().matrix(), Matrix4::from_scale(1.));
+}
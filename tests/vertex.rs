@@ -0,0 +1,43 @@
+use draw3d::vertex::{compute_normals, Vertex};
+
+fn vertices(positions: &[[f32; 3]]) -> Vec<Vertex> {
+    positions.iter().map(|&p| Vertex::new(p, [1., 1., 1.])).collect()
+}
+
+fn close(a: [f32; 3], b: [f32; 3]) -> bool {
+    (0..3).all(|k| (a[k] - b[k]).abs() < 1e-6)
+}
+
+#[test]
+fn normals_follow_the_winding() {
+    let mut square = vertices(&[[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]]);
+    compute_normals(&mut square, &[0, 1, 2, 0, 2, 3]);
+    assert!(square.iter().all(|v| v.normal == [0., 0., 1.]));
+
+    // Winding the other way turns them around.
+    compute_normals(&mut square, &[0, 2, 1, 0, 3, 2]);
+    assert!(square.iter().all(|v| v.normal == [0., 0., -1.]));
+}
+
+#[test]
+fn shared_vertices_average_by_area() {
+    // A large triangle facing +z and a small one facing +x share the edge along y.
+    let mut fold = vertices(&[[0., 0., 0.], [0., 1., 0.], [-3., 0., 0.], [0., 0., 1.]]);
+    compute_normals(&mut fold, &[0, 1, 2, 0, 1, 3]);
+    // The large triangle has three times the area, so it pulls three times as hard.
+    let expected = [1. / 10f32.sqrt(), 0., 3. / 10f32.sqrt()];
+    assert!(close(fold[0].normal, expected), "{:?}", fold[0].normal);
+    assert!(close(fold[1].normal, expected));
+    assert_eq!(fold[2].normal, [0., 0., 1.]);
+    assert_eq!(fold[3].normal, [1., 0., 0.]);
+}
+
+#[test]
+fn unused_and_degenerate_vertices_keep_their_normals() {
+    let mut line = vertices(&[[0., 0., 0.], [1., 0., 0.], [2., 0., 0.], [5., 5., 5.]]);
+    line[3].normal = [0., 1., 0.];
+    compute_normals(&mut line, &[0, 1, 2]);
+    // A triangle without area has no direction, so its vertices stay unlit.
+    assert!(line[..3].iter().all(|v| v.normal == [0.; 3]));
+    assert_eq!(line[3].normal, [0., 1., 0.]);
+}